@@ -0,0 +1,398 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Configuration file
+//!
+//! Options may be stored in `htop.toml` file, either found in the working directory
+//! or given with `--config`. Top-level keys are the defaults, `[profile.name]` tables
+//! hold named profiles selected with `--profile`. Keys are the long option names,
+//! like `paper`, `margin-top` or `header-template`, underscores may be used instead of dashes.
+//!
+//! ```toml
+//! background = true
+//! paper = "A4"
+//!
+//! [profile.invoice]
+//! paper = "Letter"
+//! margin = "1cm 2cm"
+//! footer-template = "templates/footer.html"
+//! var = ["company=ACME", "year=2023"]
+//! ```
+//!
+//! Values are strings, numbers, booleans or arrays of those values.
+
+use crate::errors::{err_invalid_config, err_read_file, err_unknown_profile, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Name of the configuration file looked up in the working directory.
+pub const CONFIG_FILE_NAME: &str = "htop.toml";
+
+/// Value of the option in configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+  /// String value, numbers are kept as strings too, like given on the command line.
+  String(String),
+  /// Boolean value, used for flags.
+  Boolean(bool),
+  /// Array of values, used for repeated options.
+  Array(Vec<String>),
+}
+
+/// Origin of the option value in configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigOrigin {
+  /// Top-level keys of the configuration file.
+  Defaults,
+  /// Table of the named profile.
+  Profile(String),
+}
+
+/// Single option set in configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSetting {
+  /// Long name of the option.
+  pub name: String,
+  /// Value of the option.
+  pub value: ConfigValue,
+  /// Where the value was defined.
+  pub origin: ConfigOrigin,
+}
+
+/// Parsed configuration file.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+  /// Path of the configuration file.
+  path: PathBuf,
+  /// Top-level options, in the order of definition.
+  defaults: Vec<(String, ConfigValue)>,
+  /// Options of named profiles, in the order of definition.
+  profiles: BTreeMap<String, Vec<(String, ConfigValue)>>,
+}
+
+impl Config {
+  /// Returns the path of `htop.toml` in the working directory, when such file exists.
+  pub fn find() -> Option<PathBuf> {
+    let path = Path::new(CONFIG_FILE_NAME);
+    path.is_file().then(|| path.to_path_buf())
+  }
+
+  /// Loads and parses the configuration file.
+  pub fn load(path: &Path) -> Result<Self> {
+    let content = fs::read_to_string(path).map_err(|e| err_read_file(path, e))?;
+    Self::parse(path, &content)
+  }
+
+  /// Parses the content of configuration file, the path is used in error messages.
+  pub fn parse(path: &Path, content: &str) -> Result<Self> {
+    let table = content.parse::<Table>().map_err(|e| {
+      let line = e
+        .span()
+        .map_or(1, |span| content[..span.start].matches('\n').count() + 1);
+      err_invalid_config(path, &format!("{} at line {}", e.message().trim_end(), line))
+    })?;
+    let mut config = Self {
+      path: path.to_path_buf(),
+      ..Default::default()
+    };
+    let mut defaults = Table::new();
+    for (key, value) in table {
+      match (key.as_str(), value) {
+        ("profile", Value::Table(profiles)) => {
+          for (name, profile) in profiles {
+            let Value::Table(profile) = profile else {
+              return Err(err_invalid_config(
+                path,
+                &format!("profile '{}' is not a table, expected [profile.{}]", name, name),
+              ));
+            };
+            let settings = settings(path, profile)?;
+            config.profiles.insert(name, settings);
+          }
+        }
+        (_, Value::Table(_)) => {
+          return Err(err_invalid_config(
+            path,
+            &format!("unsupported table '{}', expected [profile.name]", key),
+          ))
+        }
+        (_, value) => {
+          defaults.insert(key, value);
+        }
+      }
+    }
+    config.defaults = settings(path, defaults)?;
+    Ok(config)
+  }
+
+  /// Returns the path of the configuration file.
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Returns the options of the selected profile followed by the defaults
+  /// not overridden by the profile.
+  pub fn settings(&self, profile: Option<&str>) -> Result<Vec<ConfigSetting>> {
+    let mut settings = vec![];
+    if let Some(profile) = profile {
+      let profile_settings = self
+        .profiles
+        .get(profile)
+        .ok_or_else(|| err_unknown_profile(&self.path, profile))?;
+      for (name, value) in profile_settings {
+        settings.push(ConfigSetting {
+          name: name.to_owned(),
+          value: value.to_owned(),
+          origin: ConfigOrigin::Profile(profile.to_owned()),
+        });
+      }
+    }
+    for (name, value) in &self.defaults {
+      if !settings.iter().any(|setting| setting.name == *name) {
+        settings.push(ConfigSetting {
+          name: name.to_owned(),
+          value: value.to_owned(),
+          origin: ConfigOrigin::Defaults,
+        });
+      }
+    }
+    Ok(settings)
+  }
+}
+
+/// Converts options of the table into names and values.
+fn settings(path: &Path, table: Table) -> Result<Vec<(String, ConfigValue)>> {
+  let mut settings = vec![];
+  for (key, value) in table {
+    let name = key.replace('_', "-");
+    let unsupported = || {
+      err_invalid_config(
+        path,
+        &format!(
+          "unsupported value of option '{}', expected a string, number, boolean or an array of those",
+          name
+        ),
+      )
+    };
+    let value = match value {
+      Value::Boolean(value) => ConfigValue::Boolean(value),
+      Value::Array(items) => ConfigValue::Array(
+        items
+          .into_iter()
+          .map(|item| scalar(item).ok_or_else(unsupported))
+          .collect::<Result<_>>()?,
+      ),
+      value => ConfigValue::String(scalar(value).ok_or_else(unsupported)?),
+    };
+    settings.push((name, value));
+  }
+  // names with underscores and dashes are the same option
+  if let Some(name) = duplicate(&settings) {
+    return Err(err_invalid_config(
+      path,
+      &format!("option '{}' is defined more than once", name),
+    ));
+  }
+  Ok(settings)
+}
+
+/// Converts a string, number or boolean into a string, like given on the command line.
+fn scalar(value: Value) -> Option<String> {
+  match value {
+    Value::String(value) => Some(value),
+    Value::Integer(value) => Some(value.to_string()),
+    Value::Float(value) => Some(value.to_string()),
+    Value::Boolean(value) => Some(value.to_string()),
+    _ => None,
+  }
+}
+
+/// Returns the name of the first option defined more than once.
+fn duplicate(settings: &[(String, ConfigValue)]) -> Option<&str> {
+  settings
+    .iter()
+    .enumerate()
+    .find(|(index, (name, _))| settings[..*index].iter().any(|(defined, _)| defined == name))
+    .map(|(_, (name, _))| name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Parses the configuration from a file named `htop.toml`.
+  fn parse(content: &str) -> Result<Config> {
+    Config::parse(Path::new(CONFIG_FILE_NAME), content)
+  }
+
+  /// Returns the names and values of settings of the profile.
+  fn values(config: &Config, profile: Option<&str>) -> Vec<(String, ConfigValue)> {
+    config
+      .settings(profile)
+      .unwrap()
+      .into_iter()
+      .map(|setting| (setting.name, setting.value))
+      .collect()
+  }
+
+  /// Creates a string value.
+  fn string(value: &str) -> ConfigValue {
+    ConfigValue::String(value.to_string())
+  }
+
+  /// Asserts that parsing fails with the error message containing expected text.
+  fn assert_invalid(content: &str, expected: &str) {
+    match parse(content) {
+      Err(error) => assert!(error.to_string().contains(expected), "{}: {}", content, error),
+      Ok(config) => panic!("{} parsed into {:?}", content, config),
+    }
+  }
+
+  #[test]
+  fn defaults_and_profiles_are_parsed() {
+    let config = parse(
+      r#"
+      # defaults
+      background = true
+      paper = "A4"
+      scale = 0.8
+      jobs = 4
+
+      [profile.invoice]
+      paper = 'Letter'
+      margin_top = "1cm"
+      var = ["company=ACME", "year=2023"]
+      "#,
+    )
+    .unwrap();
+    assert_eq!(
+      values(&config, None),
+      [
+        ("background".to_string(), ConfigValue::Boolean(true)),
+        ("paper".to_string(), string("A4")),
+        ("scale".to_string(), string("0.8")),
+        ("jobs".to_string(), string("4")),
+      ]
+    );
+    assert_eq!(
+      values(&config, Some("invoice")),
+      [
+        ("paper".to_string(), string("Letter")),
+        ("margin-top".to_string(), string("1cm")),
+        (
+          "var".to_string(),
+          ConfigValue::Array(vec!["company=ACME".to_string(), "year=2023".to_string()])
+        ),
+        ("background".to_string(), ConfigValue::Boolean(true)),
+        ("scale".to_string(), string("0.8")),
+        ("jobs".to_string(), string("4")),
+      ]
+    );
+  }
+
+  #[test]
+  fn settings_keep_their_origin() {
+    let config = parse("paper = \"A4\"\nlandscape = true\n[profile.wide]\npaper = \"A3\"\n").unwrap();
+    let origins = config
+      .settings(Some("wide"))
+      .unwrap()
+      .into_iter()
+      .map(|setting| (setting.name, setting.origin))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      origins,
+      [
+        ("paper".to_string(), ConfigOrigin::Profile("wide".to_string())),
+        ("landscape".to_string(), ConfigOrigin::Defaults),
+      ]
+    );
+  }
+
+  #[test]
+  fn unknown_profile_is_an_error() {
+    let config = parse("paper = \"A4\"").unwrap();
+    assert!(config.settings(Some("missing")).is_err());
+  }
+
+  #[test]
+  fn escape_sequences_are_decoded() {
+    let config = parse(r#"header-template = "<span>\u00e9\t\U0001F600\"</span>""#).unwrap();
+    assert_eq!(
+      values(&config, None),
+      [(
+        "header-template".to_string(),
+        string("<span>\u{e9}\t\u{1F600}\"</span>")
+      )]
+    );
+  }
+
+  #[test]
+  fn truncated_escape_sequences_are_rejected() {
+    for content in [
+      "paper = \"\\u41",
+      "paper = \"\\U0041",
+      "paper = \"\\u41\"",
+      "paper = \"\\",
+    ] {
+      assert_invalid(content, "invalid configuration in htop.toml");
+    }
+  }
+
+  #[test]
+  fn syntax_errors_report_the_line() {
+    assert_invalid("paper = \"A4\"\n\nmargin = ", "at line 3");
+    assert_invalid("paper = A4", "at line 1");
+  }
+
+  #[test]
+  fn options_defined_twice_are_rejected() {
+    assert_invalid("paper = \"A4\"\npaper = \"A3\"", "at line 2");
+    assert_invalid(
+      "margin-top = \"1cm\"\nmargin_top = \"2cm\"",
+      "option 'margin-top' is defined more than once",
+    );
+    assert_invalid(
+      "[profile.a]\nmargin-top = \"1cm\"\nmargin_top = \"2cm\"",
+      "option 'margin-top' is defined more than once",
+    );
+  }
+
+  #[test]
+  fn unsupported_tables_and_values_are_rejected() {
+    assert_invalid("[server]\nport = 1", "unsupported table 'server'");
+    assert_invalid("wait.timeout = 1", "unsupported table 'wait'");
+    assert_invalid("[profile]\npaper = \"A4\"", "profile 'paper' is not a table");
+    assert_invalid("paper = { width = 1 }", "unsupported table 'paper'");
+    assert_invalid("var = [[\"a\"]]", "unsupported value of option 'var'");
+    assert_invalid("created = 2023-04-05", "unsupported value of option 'created'");
+  }
+}
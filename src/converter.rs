@@ -30,45 +30,920 @@
 
 //! # HTML to PDF converter
 
-use crate::defs::{Files, HTOP_NAME};
-use crate::errors::{err_headless_chrome, err_write_file, Result};
-use crate::options::PdfPrintingOptions;
-use headless_chrome::{Browser, LaunchOptionsBuilder};
+use crate::defs::{Files, HTOP_NAME, STDIO};
+use crate::errors::{
+  err_browser_connect, err_chrome_launch, err_create_dir, err_document_width, err_invalid_browser_url,
+  err_merge_page_ranges, err_navigation, err_page_numbering, err_page_timeout, err_prepare_page, err_print,
+  err_shared_user_data_dir, err_total_timeout, err_write_file, err_write_stdout, BoxedError, Result,
+};
+use crate::margins::Margins;
+use crate::metadata::{collect_metadata, document_metadata, set_metadata};
+use crate::options::{check_scale, parse_page_ranges, PdfPrintingOptions};
+use crate::outline::{add_outline, collect_headings};
+use crate::paper::{Paper, PX_PER_INCH};
+use crate::pdf::{merge, Chapter, Document};
+use crate::utils::{file_url, html_data_url, message};
+use crate::waits::{wait_for, NetworkMonitor, Wait, WaitCondition};
+use headless_chrome::protocol::cdp::Emulation;
+use headless_chrome::{Browser, LaunchOptionsBuilder, Tab};
+use serde_json::json;
 use std::ffi::OsStr;
 use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Builder for [Converter].
+#[derive(Default)]
+pub struct ConverterBuilder {
+  /// PDF printing options passed to created converter.
+  options: PdfPrintingOptions,
+}
+
+impl ConverterBuilder {
+  /// Creates a new builder initialized with specified printing options.
+  pub fn new(options: PdfPrintingOptions) -> Self {
+    Self { options }
+  }
+
+  /// Sets the paper orientation, `true` = landscape, `false` = portrait.
+  pub fn landscape(mut self, landscape: bool) -> Self {
+    self.options.landscape = landscape;
+    self
+  }
+
+  /// Sets the flag indicating if background should be printed.
+  pub fn print_background(mut self, print_background: bool) -> Self {
+    self.options.print_background = print_background;
+    self
+  }
+
+  /// Sets the paper size.
+  pub fn paper(mut self, paper: Paper) -> Self {
+    self.options.paper_width = paper.width();
+    self.options.paper_height = paper.height();
+    self
+  }
+
+  /// Sets the page margins.
+  pub fn margins(mut self, margins: Margins) -> Self {
+    self.options.margin_top = Some(margins.top);
+    self.options.margin_bottom = Some(margins.bottom);
+    self.options.margin_left = Some(margins.left);
+    self.options.margin_right = Some(margins.right);
+    self
+  }
+
+  /// Sets the scale of the page rendering, allowed values are between 0.1 and 2.0.
+  pub fn scale(mut self, scale: f64) -> Self {
+    self.options.scale = Some(scale);
+    self
+  }
+
+  /// Sets the flag indicating if the scale should be computed to fit the document into printable width.
+  pub fn fit_width(mut self, fit_width: bool) -> Self {
+    self.options.fit_width = fit_width;
+    self
+  }
+
+  /// Sets the flag indicating if page size defined in CSS `@page` rule takes precedence over the paper size.
+  pub fn prefer_css_page_size(mut self, prefer_css_page_size: bool) -> Self {
+    self.options.prefer_css_page_size = prefer_css_page_size;
+    self
+  }
+
+  /// Sets the ranges of pages to print, like `1-3,5,8-`.
+  pub fn page_ranges(mut self, page_ranges: &str) -> Self {
+    self.options.page_ranges = Some(page_ranges.to_string());
+    self
+  }
+
+  /// Sets the flag indicating if invalid page ranges should be silently ignored.
+  pub fn ignore_invalid_page_ranges(mut self, ignore_invalid_page_ranges: bool) -> Self {
+    self.options.ignore_invalid_page_ranges = ignore_invalid_page_ranges;
+    self
+  }
+
+  /// Sets the `HTML` template of the page header.
+  pub fn header_template(mut self, header_template: &str) -> Self {
+    self.options.header_template = Some(header_template.to_string());
+    self
+  }
+
+  /// Sets the `HTML` template of the page footer.
+  pub fn footer_template(mut self, footer_template: &str) -> Self {
+    self.options.footer_template = Some(footer_template.to_string());
+    self
+  }
+
+  /// Adds the condition the page must satisfy before printing, checked with specified timeout.
+  ///
+  /// Conditions are checked in the order they were added.
+  pub fn wait_for(mut self, condition: WaitCondition, timeout: Duration) -> Self {
+    self.options.waits.push(Wait::new(condition, timeout));
+    self
+  }
+
+  /// Sets the maximal time of loading, waiting for and printing a single page.
+  pub fn page_timeout(mut self, page_timeout: Duration) -> Self {
+    self.options.page_timeout = Some(page_timeout);
+    self
+  }
+
+  /// Sets the maximal time of converting all files with [html_to_pdf].
+  pub fn total_timeout(mut self, total_timeout: Duration) -> Self {
+    self.options.total_timeout = Some(total_timeout);
+    self
+  }
+
+  /// Sets the title written into printed documents, instead of the title of the page.
+  pub fn title(mut self, title: &str) -> Self {
+    self.options.metadata.title = Some(title.to_string());
+    self
+  }
+
+  /// Sets the author written into printed documents, instead of the author given in page metadata.
+  pub fn author(mut self, author: &str) -> Self {
+    self.options.metadata.author = Some(author.to_string());
+    self
+  }
+
+  /// Sets the subject written into printed documents, instead of the description given in page metadata.
+  pub fn subject(mut self, subject: &str) -> Self {
+    self.options.metadata.subject = Some(subject.to_string());
+    self
+  }
+
+  /// Sets the keywords written into printed documents, instead of the keywords given in page metadata.
+  pub fn keywords(mut self, keywords: &str) -> Self {
+    self.options.metadata.keywords = Some(keywords.to_string());
+    self
+  }
+
+  /// Sets the flag indicating if printed documents are kept without metadata taken from the page.
+  pub fn no_metadata(mut self, no_metadata: bool) -> Self {
+    self.options.no_metadata = no_metadata;
+    self
+  }
+
+  /// Sets heading levels included in the outline added to printed documents.
+  pub fn outline(mut self, levels: RangeInclusive<u8>) -> Self {
+    self.options.outline_levels = Some(levels);
+    self
+  }
+
+  /// Sets the flag indicating if printing process should be more _talkative_.
+  pub fn verbose(mut self, verbose: bool) -> Self {
+    self.options.verbose = verbose;
+    self
+  }
+
+  /// Sets the number of browser tabs used for converting files in parallel.
+  pub fn jobs(mut self, jobs: usize) -> Self {
+    self.options.jobs = jobs;
+    self
+  }
+
+  /// Sets the number of browser processes the tabs are distributed among.
+  pub fn browsers(mut self, browsers: usize) -> Self {
+    self.options.browsers = browsers;
+    self
+  }
+
+  /// Sets the flag indicating if remaining files should be converted after a failure.
+  pub fn keep_going(mut self, keep_going: bool) -> Self {
+    self.options.keep_going = keep_going;
+    self
+  }
+
+  /// Sets the flag indicating if crash reporter should be disabled.
+  pub fn no_crash_reports(mut self, no_crash_reports: bool) -> Self {
+    self.options.no_crash_reports = no_crash_reports;
+    self
+  }
+
+  /// Sets the path of Chrome or Chromium executable, instead of the automatically detected one.
+  pub fn chrome_path(mut self, chrome_path: impl Into<PathBuf>) -> Self {
+    self.options.chrome_path = Some(chrome_path.into());
+    self
+  }
+
+  /// Adds a command-line argument passed to the browser, like `--disable-gpu`.
+  pub fn chrome_arg(mut self, chrome_arg: &str) -> Self {
+    self.options.chrome_args.push(chrome_arg.to_string());
+    self
+  }
+
+  /// Sets the directory of the browser profile, instead of a new temporary directory.
+  pub fn user_data_dir(mut self, user_data_dir: impl Into<PathBuf>) -> Self {
+    self.options.user_data_dir = Some(user_data_dir.into());
+    self
+  }
+
+  /// Sets the flag indicating if the browser should be run without the sandbox.
+  pub fn no_sandbox(mut self, no_sandbox: bool) -> Self {
+    self.options.no_sandbox = no_sandbox;
+    self
+  }
+
+  /// Sets the proxy server used by the browser, like `http://proxy:3128`.
+  pub fn proxy_server(mut self, proxy_server: &str) -> Self {
+    self.options.proxy_server = Some(proxy_server.to_string());
+    self
+  }
+
+  /// Sets the width and height of the browser window in pixels.
+  pub fn window_size(mut self, width: u32, height: u32) -> Self {
+    self.options.window_size = Some((width, height));
+    self
+  }
+
+  /// Sets DevTools endpoint of already running browser used instead of launching a new one,
+  /// like `ws://127.0.0.1:9222/devtools/browser/<id>` or `http://127.0.0.1:9222`.
+  pub fn browser_url(mut self, browser_url: &str) -> Self {
+    self.options.browser_url = Some(browser_url.to_string());
+    self
+  }
+
+  /// Sets remote debugging port of already running browser used instead of launching a new one.
+  pub fn browser_port(mut self, browser_port: u16) -> Self {
+    self.options.browser_port = Some(browser_port);
+    self
+  }
+
+  /// Launches the browser, or attaches to already running one, and creates a new [Converter].
+  pub fn build(self) -> Result<Converter> {
+    Converter::new(self.options)
+  }
+}
+
+/// Time after which the connection to the browser not sending any events is closed.
+const DEFAULT_IDLE_BROWSER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximal time of reading the DevTools endpoint of already running browser.
+const DEVTOOLS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Script adding hidden links to all elements having an identifier, so headless chrome
+/// prints named destinations for them, used as targets of links between merged pages.
+const LINK_TARGETS_SCRIPT: &str = r#"(() => {
+  if (!document.body) return;
+  const links = document.createElement('div');
+  links.style.display = 'none';
+  for (const element of document.querySelectorAll('[id], a[name]')) {
+    const link = document.createElement('a');
+    link.href = '#' + encodeURIComponent(element.id || element.name);
+    links.appendChild(link);
+  }
+  document.body.appendChild(links);
+})()"#;
+
+/// Returns the script adding blank pages before and after the document content.
+fn blank_pages_script(before: usize, after: usize) -> String {
+  format!(
+    r#"(() => {{
+  if (!document.body) return;
+  const blank = (property) => {{
+    const page = document.createElement('div');
+    page.style.setProperty(property, 'page');
+    return page;
+  }};
+  for (let i = 0; i < {before}; i++) document.body.prepend(blank('break-after'));
+  for (let i = 0; i < {after}; i++) document.body.append(blank('break-before'));
+}})()"#
+  )
+}
+
+/// Attaches to already running browser or launches headless chrome, depending on printing options.
+///
+/// The connection to the browser is closed when no events were received for `idle_browser_timeout`.
+pub(crate) fn open_browser(options: &PdfPrintingOptions, idle_browser_timeout: Duration) -> Result<Browser> {
+  match browser_endpoint(options)? {
+    Some(endpoint) => Browser::connect_with_timeout(endpoint.clone(), idle_browser_timeout)
+      .map_err(|e| err_browser_connect(&endpoint, e)),
+    None => launch_browser(options, idle_browser_timeout),
+  }
+}
+
+/// Launches headless chrome with launch options taken from printing options.
+fn launch_browser(options: &PdfPrintingOptions, idle_browser_timeout: Duration) -> Result<Browser> {
+  let mut arguments = vec![];
+  if options.no_crash_reports {
+    arguments.push(OsStr::new("--disable-crash-reporter"));
+  }
+  arguments.extend(options.chrome_args.iter().map(OsStr::new));
+  let launch_options = LaunchOptionsBuilder::default()
+    .path(options.chrome_path.clone())
+    .user_data_dir(options.user_data_dir.clone())
+    .sandbox(!options.no_sandbox)
+    .proxy_server(options.proxy_server.as_deref())
+    .window_size(options.window_size)
+    .idle_browser_timeout(idle_browser_timeout)
+    .args(arguments)
+    .build()
+    .map_err(err_chrome_launch)?;
+  Browser::new(launch_options).map_err(err_chrome_launch)
+}
+
+/// Returns the WebSocket URL of DevTools endpoint of already running browser,
+/// or `None` when the browser should be launched.
+fn browser_endpoint(options: &PdfPrintingOptions) -> Result<Option<String>> {
+  if let Some(port) = options.browser_port {
+    return debugger_url(&format!("127.0.0.1:{}", port)).map(Some);
+  }
+  let Some(url) = options.browser_url.as_deref() else {
+    return Ok(None);
+  };
+  let scheme = url.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+  match (scheme.as_deref(), url.split_once("://")) {
+    (Some("ws" | "wss"), _) => Ok(Some(url.to_string())),
+    (Some("http"), Some((_, rest))) => {
+      let address = rest.split('/').next().unwrap_or_default();
+      debugger_url(address).map(Some)
+    }
+    _ => Err(err_invalid_browser_url(url)),
+  }
+}
+
+/// Reads the WebSocket URL of DevTools endpoint from `/json/version`
+/// served by the browser listening on specified address.
+fn debugger_url(address: &str) -> Result<String> {
+  let endpoint = format!("http://{}/json/version", address);
+  let mut stream = TcpStream::connect(address).map_err(|e| err_browser_connect(&endpoint, e))?;
+  stream
+    .set_read_timeout(Some(DEVTOOLS_TIMEOUT))
+    .map_err(|e| err_browser_connect(&endpoint, e))?;
+  write!(
+    stream,
+    "GET /json/version HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+    address
+  )
+  .map_err(|e| err_browser_connect(&endpoint, e))?;
+  let mut response = String::new();
+  stream
+    .read_to_string(&mut response)
+    .map_err(|e| err_browser_connect(&endpoint, e))?;
+  let (_, body) = response.split_once("\r\n\r\n").unwrap_or_default();
+  serde_json::from_str::<serde_json::Value>(body)
+    .ok()
+    .and_then(|version| Some(version.get("webSocketDebuggerUrl")?.as_str()?.to_string()))
+    .ok_or_else(|| err_browser_connect(&endpoint, "missing webSocketDebuggerUrl in the response"))
+}
+
+/// HTML to PDF converter.
+///
+/// Converter owns a headless Chrome browser, which is launched once
+/// when the converter is created and closed when the converter is dropped.
+/// When attached to already running browser, only the tab opened by the converter
+/// is closed and the browser is left running.
+pub struct Converter {
+  /// Headless Chrome browser.
+  browser: Browser,
+  /// Browser tab used for printing, replaced with a new one when printing a page times out.
+  tab: Mutex<Arc<PrintingTab>>,
+  /// PDF printing options.
+  options: PdfPrintingOptions,
+  /// Flag indicating if the browser outlives the converter, like already running browser.
+  shared: bool,
+}
+
+impl Converter {
+  /// Returns a builder for [Converter] with default printing options.
+  pub fn builder() -> ConverterBuilder {
+    ConverterBuilder::default()
+  }
+
+  /// Launches the browser, or attaches to already running one,
+  /// and creates a new converter with specified printing options.
+  pub fn new(options: PdfPrintingOptions) -> Result<Self> {
+    if let Some(scale) = options.scale {
+      check_scale(scale)?;
+    }
+    if let Some(page_ranges) = &options.page_ranges {
+      parse_page_ranges(page_ranges)?;
+    }
+    let shared = options.attaches_browser();
+    let browser = open_browser(&options, DEFAULT_IDLE_BROWSER_TIMEOUT)?;
+    Self::with_browser(browser, options, shared)
+  }
+
+  /// Creates a new converter printing in a new tab of specified browser.
+  ///
+  /// When the browser is shared, it outlives the converter and the tab is closed when the converter is dropped.
+  pub(crate) fn with_browser(browser: Browser, options: PdfPrintingOptions, shared: bool) -> Result<Self> {
+    let tab = PrintingTab::open(&browser, &options)?;
+    Ok(Self {
+      browser,
+      tab: Mutex::new(Arc::new(tab)),
+      options,
+      shared,
+    })
+  }
+
+  /// Creates a new converter sharing the browser with this converter, but printing in a separate tab.
+  ///
+  /// Converters sharing the browser may print simultaneously from different threads.
+  pub fn with_new_tab(&self) -> Result<Self> {
+    Self::with_browser(self.browser.clone(), self.options.clone(), self.shared)
+  }
+
+  /// Returns PDF printing options used by this converter.
+  pub fn options(&self) -> &PdfPrintingOptions {
+    &self.options
+  }
+
+  /// Returns the headless Chrome browser owned by this converter.
+  pub fn browser(&self) -> &Browser {
+    &self.browser
+  }
+
+  /// Converts the page loaded from specified URL into `PDF` bytes.
+  ///
+  /// When the page timeout is set in printing options and loading, waiting or printing
+  /// takes longer, the page is abandoned, the tab is replaced with a new one
+  /// and the [Timeout](crate::errors::HtopError::Timeout) error is returned.
+  pub fn convert_url(&self, url: &str) -> Result<Vec<u8>> {
+    self.convert_url_within(url, self.options.page_timeout)
+  }
+
+  /// Converts the page loaded from specified URL into `PDF` bytes within specified time.
+  pub(crate) fn convert_url_within(&self, url: &str, timeout: Option<Duration>) -> Result<Vec<u8>> {
+    self.print_url(url, None, timeout)
+  }
+
+  /// Prints the page loaded from specified URL, running the script in the page before printing.
+  fn print_url(&self, url: &str, script: Option<&str>, timeout: Option<Duration>) -> Result<Vec<u8>> {
+    let tab = Arc::clone(&self.tab.lock().unwrap());
+    let Some(timeout) = timeout else {
+      return tab.print(url, &self.options, script);
+    };
+    let (sender, receiver) = mpsc::channel();
+    let printing_tab = Arc::clone(&tab);
+    let printing_url = url.to_string();
+    let printing_script = script.map(str::to_string);
+    let options = self.options.clone();
+    thread::spawn(move || {
+      let _ = sender.send(printing_tab.print(&printing_url, &options, printing_script.as_deref()));
+    });
+    match receiver.recv_timeout(timeout) {
+      Ok(result) => result,
+      Err(_) => {
+        if self.options.verbose {
+          message!("[{HTOP_NAME}] Abandoning page {} and recycling the tab", url);
+        }
+        // the abandoned printing fails when its tab is closed
+        let _ = tab.tab.close(false);
+        *self.tab.lock().unwrap() = Arc::new(PrintingTab::open(&self.browser, &self.options)?);
+        Err(err_page_timeout(url, timeout))
+      }
+    }
+  }
+
+  /// Converts the page loaded from specified URL into `PDF` file.
+  pub fn convert_url_to_file<P: AsRef<Path>>(&self, url: &str, output_file: P) -> Result<()> {
+    let pdf = self.convert_url(url)?;
+    write_pdf(output_file.as_ref(), pdf)
+  }
+
+  /// Converts `HTML` file into `PDF` bytes.
+  pub fn convert_file<P: AsRef<Path>>(&self, input_file: P) -> Result<Vec<u8>> {
+    self.convert_url(&file_url(input_file.as_ref())?)
+  }
+
+  /// Converts `HTML` file into `PDF` file.
+  pub fn convert_file_to_file<P: AsRef<Path>, Q: AsRef<Path>>(&self, input_file: P, output_file: Q) -> Result<()> {
+    let pdf = self.convert_file(input_file)?;
+    write_pdf(output_file.as_ref(), pdf)
+  }
+
+  /// Converts pages loaded from specified URLs into a single `PDF` document, pages follow in the order of URLs.
+  ///
+  /// Page numbers in header and footer templates continue across merged pages,
+  /// and links between merged pages become internal links of the document.
+  ///
+  /// Headless chrome numbers pages of each printed document from 1, so when templates contain
+  /// `pageNumber` or `totalPages` classes, every page except a single one is printed twice:
+  /// first to count its pages, then again between blank pages standing for the pages
+  /// of other merged documents. Page numbers are drawn into page contents, so they can not
+  /// be changed after printing, and merging such documents takes about twice as long.
+  /// When the second print has a different number of pages, (e.g. the page changes between loads),
+  /// merging fails rather than numbering pages wrongly.
+  pub fn merge_urls(&self, urls: &[String]) -> Result<Vec<u8>> {
+    if self.options.page_ranges.is_some() {
+      return Err(err_merge_page_ranges());
+    }
+    let verbose = self.options.verbose;
+    let mut documents = Vec::with_capacity(urls.len());
+    for url in urls {
+      if verbose {
+        message!("[{HTOP_NAME}] Printing {}", url);
+      }
+      let pdf = self.print_url(url, Some(LINK_TARGETS_SCRIPT), self.options.page_timeout)?;
+      documents.push(Document::load(&pdf)?);
+    }
+    if self.has_page_numbers() && documents.len() > 1 {
+      // each page is printed again between blank pages, so the page numbers
+      // printed by headless chrome continue from the preceding pages
+      let counts = documents.iter().map(Document::page_count).collect::<Result<Vec<_>>>()?;
+      let total = counts.iter().sum::<usize>();
+      let mut before = 0;
+      for (index, url) in urls.iter().enumerate() {
+        let count = counts[index];
+        if count > 0 {
+          if verbose {
+            message!(
+              "[{HTOP_NAME}] Numbering pages {}-{} of {}",
+              before + 1,
+              before + count,
+              url
+            );
+          }
+          let script = format!(
+            "{};\n{}",
+            LINK_TARGETS_SCRIPT,
+            blank_pages_script(before, total - before - count)
+          );
+          let mut document = Document::load(&self.print_url(url, Some(&script), self.options.page_timeout)?)?;
+          let printed = document.page_count()?;
+          if printed != total {
+            return Err(err_page_numbering(url, total, printed));
+          }
+          document.retain_pages(before..before + count)?;
+          documents[index] = document;
+        }
+        before += count;
+      }
+    }
+    let chapters = urls
+      .iter()
+      .zip(documents)
+      .map(|(url, document)| Chapter {
+        url: url.to_owned(),
+        document,
+      })
+      .collect();
+    merge(chapters)?.save()
+  }
+
+  /// Converts pages loaded from specified URLs into a single `PDF` file, see [Converter::merge_urls].
+  pub fn merge_urls_to_file<P: AsRef<Path>>(&self, urls: &[String], output_file: P) -> Result<()> {
+    let pdf = self.merge_urls(urls)?;
+    write_pdf(output_file.as_ref(), pdf)
+  }
+
+  /// Returns `true` when header or footer template prints page numbers.
+  fn has_page_numbers(&self) -> bool {
+    [&self.options.header_template, &self.options.footer_template]
+      .into_iter()
+      .flatten()
+      .any(|template| template.contains("pageNumber") || template.contains("totalPages"))
+  }
+
+  /// Converts `HTML` document given as a string into `PDF` bytes.
+  pub fn convert_html_string(&self, html: &str) -> Result<Vec<u8>> {
+    self.convert_url(&html_data_url(html))
+  }
+
+  /// Converts `HTML` document given as a string into `PDF` file.
+  pub fn convert_html_string_to_file<P: AsRef<Path>>(&self, html: &str, output_file: P) -> Result<()> {
+    let pdf = self.convert_html_string(html)?;
+    write_pdf(output_file.as_ref(), pdf)
+  }
+}
+
+impl Drop for Converter {
+  /// Closes the tab opened in shared browser, which is left running.
+  fn drop(&mut self) {
+    if self.shared {
+      if let Ok(tab) = self.tab.lock() {
+        let _ = tab.tab.close(false);
+      }
+    }
+  }
+}
+
+/// Browser tab used for printing.
+struct PrintingTab {
+  /// Headless chrome tab.
+  tab: Arc<Tab>,
+  /// Monitor of network requests, attached only when waiting for network idle.
+  network: Option<NetworkMonitor>,
+}
+
+impl PrintingTab {
+  /// Opens a new tab in the browser.
+  fn open(browser: &Browser, options: &PdfPrintingOptions) -> Result<Self> {
+    let tab = browser.new_tab().map_err(err_chrome_launch)?;
+    if let Some(page_timeout) = options.page_timeout {
+      // navigation must not time out earlier than the page
+      tab.set_default_timeout(page_timeout);
+    }
+    let network = if options
+      .waits
+      .iter()
+      .any(|wait| matches!(wait.condition, WaitCondition::NetworkIdle(_)))
+    {
+      Some(NetworkMonitor::attach(&tab)?)
+    } else {
+      None
+    };
+    Ok(Self { tab, network })
+  }
+
+  /// Loads the page from specified URL, waits until it is ready and prints it into `PDF` bytes.
+  ///
+  /// The script is evaluated in the page after waiting, just before printing.
+  fn print(&self, url: &str, options: &PdfPrintingOptions, script: Option<&str>) -> Result<Vec<u8>> {
+    if let Some(network) = &self.network {
+      network.reset();
+    }
+    self.tab.navigate_to(url).map_err(|e| err_navigation(url, e))?;
+    self.tab.wait_until_navigated().map_err(|e| err_navigation(url, e))?;
+    let mut options = options.clone();
+    for wait in &options.waits {
+      if options.verbose {
+        message!("[{HTOP_NAME}] Waiting for {}", wait.condition);
+      }
+      wait_for(&self.tab, wait, self.network.as_ref())?;
+    }
+    if let Some(script) = script {
+      self.tab.evaluate(script, false).map_err(err_prepare_page)?;
+    }
+    if options.prefer_css_page_size && options.verbose {
+      match self.css_page_size() {
+        Some(size) => message!("[{HTOP_NAME}] Using page size from CSS: {}", size),
+        None => message!("[{HTOP_NAME}] No page size in CSS, using paper size from command line"),
+      }
+    }
+    if options.fit_width {
+      let scale = options.fit_width_scale(self.document_width(options.printable_width() * PX_PER_INCH)?);
+      if options.verbose {
+        message!("[{HTOP_NAME}] Fitting document to page width with scale {:.3}", scale);
+      }
+      options.scale = Some(scale);
+    }
+    let headings = match &options.outline_levels {
+      Some(levels) => Some(collect_headings(&self.tab, levels)?),
+      None => None,
+    };
+    let metadata = document_metadata(&options, || collect_metadata(&self.tab))?;
+    let pdf = self.tab.print_to_pdf(Some(options.into())).map_err(err_print)?;
+    if headings.is_none() && metadata.is_none() {
+      // nothing to add, the document is kept exactly as printed
+      return Ok(pdf);
+    }
+    let mut document = Document::load(&pdf)?;
+    if let Some(headings) = headings {
+      add_outline(&mut document, &headings)?;
+    }
+    if let Some(metadata) = metadata {
+      set_metadata(&mut document, &metadata)?;
+    }
+    document.save()
+  }
+
+  /// Returns the page size declared in CSS `@page` rules of the document loaded in the tab.
+  ///
+  /// Style sheets that can not be inspected (like cross-origin ones) are skipped.
+  fn css_page_size(&self) -> Option<String> {
+    const SCRIPT: &str = r#"(() => {
+      for (const sheet of document.styleSheets) {
+        let rules;
+        try { rules = sheet.cssRules; } catch (e) { continue; }
+        for (const rule of rules) {
+          if (rule instanceof CSSPageRule && rule.style.getPropertyValue('size')) {
+            return rule.style.getPropertyValue('size');
+          }
+        }
+      }
+      return '';
+    })()"#;
+    self
+      .tab
+      .evaluate(SCRIPT, false)
+      .ok()?
+      .value
+      .and_then(|value| value.as_str().map(str::to_string))
+      .filter(|size| !size.is_empty())
+  }
+
+  /// Returns the scroll width of the document loaded in the tab, in CSS pixels,
+  /// measured with the viewport as wide as the printable area of the page.
+  ///
+  /// Laid out in the default viewport, the document is never narrower than the viewport,
+  /// so documents fitting into the page would be scaled down as well.
+  fn document_width(&self, printable_width: f64) -> Result<f64> {
+    let height = self.evaluate_number("window.innerHeight").map_err(err_document_width)?;
+    // built from JSON, so that optional parameters added in newer protocol versions are left out
+    let metrics: Emulation::SetDeviceMetricsOverride = serde_json::from_value(json!({
+      "width": printable_width.round(),
+      "height": height,
+      "deviceScaleFactor": 0,
+      "mobile": false,
+    }))
+    .map_err(err_document_width)?;
+    self.tab.call_method(metrics).map_err(err_document_width)?;
+    let width = self
+      .evaluate_number("Math.max(document.documentElement.scrollWidth, document.body ? document.body.scrollWidth : 0)");
+    self
+      .tab
+      .call_method(Emulation::ClearDeviceMetricsOverride(None))
+      .map_err(err_document_width)?;
+    width.map_err(err_document_width)
+  }
+
+  /// Evaluates the expression in the page and returns its numeric value.
+  fn evaluate_number(&self, expression: &str) -> std::result::Result<f64, BoxedError> {
+    self
+      .tab
+      .evaluate(expression, false)?
+      .value
+      .and_then(|value| value.as_f64())
+      .ok_or_else(|| format!("'{}' is not a number", expression).into())
+  }
+}
+
+/// Writes `PDF` bytes into specified file, creating missing parent directories.
+///
+/// When the file name is `-`, bytes are written to standard output.
+fn write_pdf(output_file: &Path, pdf: Vec<u8>) -> Result<()> {
+  if output_file.as_os_str() == STDIO {
+    let mut stdout = io::stdout().lock();
+    return stdout
+      .write_all(&pdf)
+      .and_then(|_| stdout.flush())
+      .map_err(err_write_stdout);
+  }
+  if let Some(parent) = output_file.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+    fs::create_dir_all(parent).map_err(|e| err_create_dir(parent, e))?;
+  }
+  fs::write(output_file, pdf).map_err(|e| err_write_file(output_file, e))
+}
+
+/// Outcome of converting a single file in a batch.
+pub struct FileReport {
+  /// URL of the input file.
+  pub input_url: String,
+  /// Name of the output file.
+  pub output_file_name: String,
+  /// Conversion time when succeeded, error when failed.
+  pub result: Result<Duration>,
+}
+
+/// Outcome of converting a batch of files.
+pub struct BatchReport {
+  /// Outcomes of converted files, in the order of input files.
+  pub files: Vec<FileReport>,
+  /// Total conversion time.
+  pub duration: Duration,
+}
+
+impl BatchReport {
+  /// Returns the number of successfully converted files.
+  pub fn succeeded(&self) -> usize {
+    self.files.iter().filter(|file| file.result.is_ok()).count()
+  }
+
+  /// Returns the number of files that failed to convert.
+  pub fn failed(&self) -> usize {
+    self.files.len() - self.succeeded()
+  }
+
+  /// Prints the table with outcomes of all converted files.
+  pub fn print_summary(&self, jobs: usize, browsers: usize) {
+    message!("[{HTOP_NAME}] Summary:");
+    for file in &self.files {
+      match &file.result {
+        Ok(duration) => message!(
+          "[{HTOP_NAME}] OK     {:>9.3}s  {}",
+          duration.as_secs_f64(),
+          file.output_file_name
+        ),
+        Err(reason) => message!("[{HTOP_NAME}] FAILED {:>10}  {}: {}", "", file.input_url, reason),
+      }
+    }
+    message!(
+      "[{HTOP_NAME}] Printed {} of {} files in {:.3}s using {} tab(s) in {} browser(s), {} failed",
+      self.succeeded(),
+      self.files.len(),
+      self.duration.as_secs_f64(),
+      jobs,
+      browsers,
+      self.failed()
+    );
+  }
+}
 
 /// Converts `HTML` input files into `PDF` output files.
-pub fn html_to_pdf(files: Files, pdf_printing_options: PdfPrintingOptions) -> Result<()> {
+///
+/// Files are distributed among `jobs` browser tabs opened in `browsers` headless chrome processes
+/// (see [PdfPrintingOptions]), each tab converts one file at a time. When any file fails
+/// and `keep_going` option is not set, files not yet started are not converted
+/// and the error of the first failed file is returned. With `keep_going` option set,
+/// all files are converted and failures are reported in returned [BatchReport].
+///
+/// When the total timeout is set, no page is printed longer than the time remaining
+/// until the whole batch times out, and files not started before are reported as timed out.
+///
+/// Nothing is printed about the batch, except messages enabled by `verbose` option;
+/// use [BatchReport::print_summary] to print the outcome and timing of each file.
+pub fn html_to_pdf(files: Files, pdf_printing_options: PdfPrintingOptions) -> Result<BatchReport> {
+  html_to_pdf_with(files, pdf_printing_options, |options| Converter::new(options.clone()))
+}
+
+/// Converts `HTML` input files into `PDF` output files like [html_to_pdf],
+/// with a converter for each browser created by `new_converter`.
+pub(crate) fn html_to_pdf_with(
+  files: Files,
+  pdf_printing_options: PdfPrintingOptions,
+  mut new_converter: impl FnMut(&PdfPrintingOptions) -> Result<Converter>,
+) -> Result<BatchReport> {
   let verbose = pdf_printing_options.verbose;
-  let no_crash_reports = pdf_printing_options.no_crash_reports;
-  let arguments = if no_crash_reports {
-    vec![OsStr::new("--disable-crash-reporter")]
-  } else {
-    vec![]
+  let keep_going = pdf_printing_options.keep_going;
+  let jobs = pdf_printing_options.jobs.clamp(1, files.len().max(1));
+  let browsers = pdf_printing_options.browsers.clamp(1, jobs);
+  let started = Instant::now();
+  let deadline = pdf_printing_options
+    .total_timeout
+    .map(|total_timeout| (started + total_timeout, total_timeout));
+  let page_timeout = |deadline: Option<(Instant, Duration)>| match deadline {
+    Some((deadline, total_timeout)) => {
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      if remaining.is_zero() {
+        Err(err_total_timeout(total_timeout))
+      } else {
+        Ok(Some(
+          pdf_printing_options
+            .page_timeout
+            .map_or(remaining, |timeout| timeout.min(remaining)),
+        ))
+      }
+    }
+    None => Ok(pdf_printing_options.page_timeout),
   };
-  let options = LaunchOptionsBuilder::default()
-    .args(arguments)
-    .build()
-    .map_err(|e| err_headless_chrome(e.to_string()))?;
-  let browser = Browser::new(options).map_err(|e| err_headless_chrome(e.to_string()))?;
-  let tab = browser.new_tab().map_err(|e| err_headless_chrome(e.to_string()))?;
-  for (input_url, output_file_name) in &files {
-    if verbose {
-      println!("[{HTOP_NAME}] Printing file {}", input_url);
-    }
-    tab
-      .navigate_to(input_url)
-      .map_err(|e| err_headless_chrome(e.to_string()))?;
-    tab
-      .wait_until_navigated()
-      .map_err(|e| err_headless_chrome(e.to_string()))?;
-    let pdf = tab
-      .print_to_pdf(Some(pdf_printing_options.into()))
-      .map_err(|e| err_headless_chrome(e.to_string()))?;
-    fs::write(output_file_name, pdf).map_err(|e| err_write_file(output_file_name, e.to_string()))?;
-    if verbose {
-      println!("[{HTOP_NAME}] Printing completed: {}\n", output_file_name);
-    }
-  }
-  Ok(())
+  if browsers > 1 && pdf_printing_options.user_data_dir.is_some() {
+    // a profile directory is locked by the first browser using it
+    return Err(err_shared_user_data_dir(browsers));
+  }
+  let mut converters = Vec::with_capacity(jobs);
+  for _ in 0..browsers {
+    converters.push(new_converter(&pdf_printing_options)?);
+  }
+  for index in browsers..jobs {
+    let converter = converters[index % browsers].with_new_tab()?;
+    converters.push(converter);
+  }
+  let next_file = AtomicUsize::new(0);
+  let results = Mutex::new(files.iter().map(|_| None).collect::<Vec<Option<Result<Duration>>>>());
+  thread::scope(|scope| {
+    for converter in &converters {
+      scope.spawn(|| loop {
+        let index = next_file.fetch_add(1, Ordering::SeqCst);
+        let Some((input_url, output_file_name)) = files.get(index) else {
+          break;
+        };
+        if verbose {
+          message!("[{HTOP_NAME}] Printing file {}", input_url);
+        }
+        let file_started = Instant::now();
+        let result = match page_timeout(deadline) {
+          Ok(timeout) => converter
+            .convert_url_within(input_url, timeout)
+            .and_then(|pdf| write_pdf(Path::new(output_file_name), pdf))
+            .map(|_| file_started.elapsed()),
+          Err(reason) => Err(reason),
+        };
+        match &result {
+          Ok(_) if verbose => message!("[{HTOP_NAME}] Printing completed: {}\n", output_file_name),
+          Err(reason) if keep_going && verbose => {
+            message!("[{HTOP_NAME}] Printing failed: {}: {}", input_url, reason)
+          }
+          // skip all files not started yet
+          Err(_) => next_file.store(files.len(), Ordering::SeqCst),
+          _ => {}
+        }
+        results.lock().unwrap()[index] = Some(result);
+      });
+    }
+  });
+  let mut report = BatchReport {
+    files: vec![],
+    duration: started.elapsed(),
+  };
+  for (result, (input_url, output_file_name)) in results.into_inner().unwrap().into_iter().zip(files) {
+    match result {
+      Some(Err(reason)) if !keep_going => return Err(reason),
+      Some(result) => report.files.push(FileReport {
+        input_url,
+        output_file_name,
+        result,
+      }),
+      None => {}
+    }
+  }
+  Ok(report)
 }
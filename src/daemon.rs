@@ -0,0 +1,851 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Conversion daemon
+//!
+//! The daemon keeps headless chrome browsers running and converts files sent by clients
+//! through a Unix domain socket, so repeated conversions do not pay the browser startup.
+//! Each connection carries a single request and a single response, both encoded as JSON
+//! on a single line. Clients parse options and collect files themselves, the daemon
+//! only prints the pages and writes `PDF` files.
+//!
+//! The socket is accessible only to the user running the daemon, connections of other users
+//! are refused, and browsers are always launched with browser options given to the daemon.
+
+use crate::converter::{BatchReport, FileReport};
+use crate::defs::{Files, HTOP_NAME, HTOP_VERSION, STDIO};
+use crate::errors::{err_canonicalize, err_daemon, HtopError, Result};
+use crate::metadata::Metadata;
+use crate::options::PdfPrintingOptions;
+use crate::utils::message;
+use crate::waits::{Wait, WaitCondition, DEFAULT_WAIT_TIMEOUT};
+use serde_json::{json, Value};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(unix)]
+use crate::converter::{html_to_pdf_with, open_browser, Converter};
+#[cfg(not(unix))]
+use crate::errors::err_daemon_unsupported;
+#[cfg(unix)]
+use crate::errors::{err_create_dir, err_daemon_running, err_socket, err_socket_dir};
+#[cfg(unix)]
+use headless_chrome::Browser;
+#[cfg(unix)]
+use std::fs;
+#[cfg(unix)]
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(unix)]
+use std::sync::Mutex;
+#[cfg(unix)]
+use std::thread;
+#[cfg(unix)]
+use std::time::Instant;
+
+/// Time after which the connection to a browser kept by the daemon is closed,
+/// when the browser did not send any events.
+#[cfg(unix)]
+const DAEMON_IDLE_BROWSER_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximal time of waiting for the response to status and stop requests.
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns the default path of the daemon socket in the runtime directory of the user,
+/// or in the private directory of the user in the temporary directory.
+pub fn default_socket_path() -> PathBuf {
+  let socket_name = format!("{HTOP_NAME}.sock");
+  match env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from) {
+    Some(runtime_dir) if runtime_dir.is_absolute() => runtime_dir.join(socket_name),
+    _ => private_socket_dir().join(socket_name),
+  }
+}
+
+/// Returns the directory of the socket in the temporary directory, distinct for each user.
+#[cfg(unix)]
+fn private_socket_dir() -> PathBuf {
+  env::temp_dir().join(format!("{HTOP_NAME}-{}", current_uid()))
+}
+
+/// Returns the directory of the socket in the temporary directory.
+#[cfg(not(unix))]
+fn private_socket_dir() -> PathBuf {
+  env::temp_dir()
+}
+
+/// Returns the effective user identifier of this process.
+#[cfg(unix)]
+fn current_uid() -> u32 {
+  // SAFETY: `geteuid` has no preconditions and always succeeds
+  unsafe { libc::geteuid() }
+}
+
+/// Status reported by the running daemon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaemonStatus {
+  /// Process identifier of the daemon.
+  pub pid: u32,
+  /// Version of the daemon.
+  pub version: String,
+  /// Time since the daemon was started.
+  pub uptime: Duration,
+  /// Number of running browsers.
+  pub browsers: usize,
+  /// Number of files converted since the daemon was started.
+  pub files: u64,
+}
+
+/// Converts files in the daemon listening on specified socket.
+///
+/// Returns `None` when no daemon is running, or the daemon could not take the job,
+/// so the files should be converted in this process. Files written to standard output
+/// are always converted in this process.
+pub fn convert(socket: &Path, files: &Files, options: &PdfPrintingOptions) -> Result<Option<BatchReport>> {
+  if files.iter().any(|(_, output_file_name)| output_file_name == STDIO) {
+    return Ok(None);
+  }
+  // output files are written by the daemon, running in another working directory
+  let current_dir = env::current_dir().map_err(|e| err_canonicalize(Path::new("."), e))?;
+  let request = json!({
+    "version": HTOP_VERSION,
+    "command": "convert",
+    "files": files
+      .iter()
+      .map(|(input_url, output_file_name)| json!([input_url, current_dir.join(output_file_name).to_string_lossy()]))
+      .collect::<Vec<Value>>(),
+    "options": encode_options(options),
+  });
+  let timeout = convert_timeout(files.len(), options);
+  let response = match send_request(socket, &request, Some(timeout)) {
+    Ok(response) => response,
+    Err(reason) => {
+      if matches!(reason.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) {
+        message!(
+          "[{HTOP_NAME}] Daemon did not respond within {:.1}s, converting in this process",
+          timeout.as_secs_f64()
+        );
+      }
+      return Ok(None);
+    }
+  };
+  if let Some(error) = response.get("error") {
+    return Err(decode_error(error));
+  }
+  let Some(mut report) = response.get("report").and_then(decode_report) else {
+    return Ok(None);
+  };
+  if report.files.len() == files.len() {
+    for (file, (_, output_file_name)) in report.files.iter_mut().zip(files) {
+      file.output_file_name = output_file_name.to_owned();
+    }
+  }
+  if options.verbose {
+    message!(
+      "[{HTOP_NAME}] Converted by daemon listening on {}",
+      socket.to_string_lossy()
+    );
+  }
+  Ok(Some(report))
+}
+
+/// Returns the status of the daemon listening on specified socket, `None` when no daemon is running.
+pub fn status(socket: &Path) -> Option<DaemonStatus> {
+  let request = json!({ "version": HTOP_VERSION, "command": "status" });
+  let response = exchange(socket, &request, Some(CONTROL_TIMEOUT))?;
+  let status = response.get("status")?;
+  Some(DaemonStatus {
+    pid: u32::try_from(status.get("pid")?.as_u64()?).ok()?,
+    version: status.get("version")?.as_str()?.to_string(),
+    uptime: Duration::from_secs_f64(status.get("uptime")?.as_f64()?),
+    browsers: usize::try_from(status.get("browsers")?.as_u64()?).ok()?,
+    files: status.get("files")?.as_u64()?,
+  })
+}
+
+/// Stops the daemon listening on specified socket, returns `false` when no daemon is running.
+///
+/// The daemon stops accepting new jobs immediately and exits when running jobs are finished.
+pub fn stop(socket: &Path) -> bool {
+  let request = json!({ "version": HTOP_VERSION, "command": "stop" });
+  exchange(socket, &request, Some(CONTROL_TIMEOUT)).is_some_and(|response| response.get("stopped").is_some())
+}
+
+/// Returns the maximal time of waiting for the daemon to convert files.
+///
+/// The time is bounded by the total timeout when given, otherwise by the page timeout,
+/// or by timeouts of wait conditions, for each file converted by a single job.
+fn convert_timeout(files: usize, options: &PdfPrintingOptions) -> Duration {
+  if let Some(total_timeout) = options.total_timeout {
+    return total_timeout.saturating_add(CONTROL_TIMEOUT);
+  }
+  let page_timeout = options.page_timeout.unwrap_or_else(|| {
+    options
+      .waits
+      .iter()
+      .map(|wait| match wait.condition {
+        WaitCondition::NetworkIdle(duration) | WaitCondition::Delay(duration) => wait.timeout.saturating_add(duration),
+        _ => wait.timeout,
+      })
+      .fold(DEFAULT_WAIT_TIMEOUT, Duration::saturating_add)
+  });
+  let rounds = files.div_ceil(options.jobs.max(1)).max(1);
+  page_timeout
+    .saturating_mul(u32::try_from(rounds).unwrap_or(u32::MAX))
+    .saturating_add(CONTROL_TIMEOUT)
+}
+
+/// Sends the request to the daemon and returns its response, `None` when no daemon is running.
+fn exchange(socket: &Path, request: &Value, timeout: Option<Duration>) -> Option<Value> {
+  send_request(socket, request, timeout).ok()
+}
+
+/// Sends the request to the daemon and returns its response.
+///
+/// Sockets not owned by the current user are refused, as the daemon listening on them
+/// could read the converted files.
+#[cfg(unix)]
+fn send_request(socket: &Path, request: &Value, timeout: Option<Duration>) -> io::Result<Value> {
+  if fs::metadata(socket)?.uid() != current_uid() {
+    message!(
+      "[{HTOP_NAME}] Ignoring daemon socket {} not owned by the current user",
+      socket.to_string_lossy()
+    );
+    return Err(ErrorKind::PermissionDenied.into());
+  }
+  let stream = UnixStream::connect(socket)?;
+  stream.set_read_timeout(timeout)?;
+  writeln!(&stream, "{}", request)?;
+  let mut line = String::new();
+  BufReader::new(&stream).read_line(&mut line)?;
+  Ok(serde_json::from_str(&line)?)
+}
+
+/// Sends the request to the daemon, always fails as the daemon is not supported on this platform.
+#[cfg(not(unix))]
+fn send_request(_socket: &Path, _request: &Value, _timeout: Option<Duration>) -> std::io::Result<Value> {
+  Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Runs the daemon listening on specified socket until it is stopped.
+///
+/// Browser options given to the daemon are used to launch all browsers, browser options
+/// of conversion jobs are ignored. The `verbose` flag enables messages about received jobs.
+#[cfg(unix)]
+pub fn serve(socket: &Path, options: &PdfPrintingOptions) -> Result<()> {
+  if status(socket).is_some() {
+    return Err(err_daemon_running(socket));
+  }
+  prepare_socket_dir(socket)?;
+  // remove the socket left by the daemon that was not stopped properly
+  let _ = fs::remove_file(socket);
+  let daemon = Daemon {
+    pool: BrowserPool::new(options),
+    started: Instant::now(),
+    files: AtomicU64::new(0),
+    stopping: AtomicBool::new(false),
+    verbose: options.verbose,
+  };
+  for index in 0..options.browsers.max(1) {
+    daemon.pool.browser(index, false)?;
+  }
+  let listener = UnixListener::bind(socket).map_err(|e| err_socket(socket, e))?;
+  fs::set_permissions(socket, fs::Permissions::from_mode(0o600)).map_err(|e| err_socket(socket, e))?;
+  let uid = current_uid();
+  if options.verbose {
+    message!("[{HTOP_NAME}] Daemon listening on {}", socket.to_string_lossy());
+  }
+  thread::scope(|scope| {
+    for stream in listener.incoming() {
+      if daemon.stopping.load(Ordering::SeqCst) {
+        break;
+      }
+      match stream {
+        Ok(stream) if peer_uid(&stream) == Some(uid) => {
+          scope.spawn(|| daemon.handle(stream, socket));
+        }
+        Ok(_) => message!("[{HTOP_NAME}] Refused connection of another user"),
+        Err(reason) => message!("[{HTOP_NAME}] Accepting connection failed: {}", reason),
+      }
+    }
+    // refuse new jobs while running jobs are finished
+    drop(listener);
+    let _ = fs::remove_file(socket);
+  });
+  if options.verbose {
+    message!("[{HTOP_NAME}] Daemon stopped");
+  }
+  Ok(())
+}
+
+/// Creates the private directory of the socket when missing, and checks that the default
+/// directory in the temporary directory is not accessible to other users.
+#[cfg(unix)]
+fn prepare_socket_dir(socket: &Path) -> Result<()> {
+  let Some(dir) = socket.parent().filter(|dir| !dir.as_os_str().is_empty()) else {
+    return Ok(());
+  };
+  if !dir.exists() {
+    fs::DirBuilder::new()
+      .mode(0o700)
+      .create(dir)
+      .map_err(|e| err_create_dir(dir, e))?;
+  }
+  if dir == private_socket_dir() {
+    let metadata = fs::symlink_metadata(dir).map_err(|e| err_create_dir(dir, e))?;
+    if !metadata.is_dir() || metadata.uid() != current_uid() || metadata.mode() & 0o077 != 0 {
+      return Err(err_socket_dir(dir));
+    }
+  }
+  Ok(())
+}
+
+/// Returns the user identifier of the process connected to the daemon.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+  use std::os::unix::io::AsRawFd;
+  let mut credentials = libc::ucred { pid: 0, uid: 0, gid: 0 };
+  let mut length = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+  // SAFETY: the buffer and its length describe a valid `ucred` structure
+  let result = unsafe {
+    libc::getsockopt(
+      stream.as_raw_fd(),
+      libc::SOL_SOCKET,
+      libc::SO_PEERCRED,
+      &mut credentials as *mut libc::ucred as *mut libc::c_void,
+      &mut length,
+    )
+  };
+  (result == 0 && length as usize == std::mem::size_of::<libc::ucred>()).then_some(credentials.uid)
+}
+
+/// Returns the user identifier of the process connected to the daemon.
+#[cfg(any(
+  target_os = "macos",
+  target_os = "ios",
+  target_os = "freebsd",
+  target_os = "openbsd",
+  target_os = "netbsd",
+  target_os = "dragonfly"
+))]
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+  use std::os::unix::io::AsRawFd;
+  let mut uid = 0;
+  let mut gid = 0;
+  // SAFETY: both pointers refer to valid identifiers
+  let result = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+  (result == 0).then_some(uid)
+}
+
+/// Returns the user identifier of the process connected to the daemon,
+/// always `None` as peer credentials are not supported on this platform.
+#[cfg(all(
+  unix,
+  not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+  ))
+))]
+fn peer_uid(_stream: &UnixStream) -> Option<u32> {
+  None
+}
+
+/// Runs the daemon, always fails as the daemon is not supported on this platform.
+#[cfg(not(unix))]
+pub fn serve(_socket: &Path, _options: &PdfPrintingOptions) -> Result<()> {
+  Err(err_daemon_unsupported())
+}
+
+/// State of the running daemon.
+#[cfg(unix)]
+struct Daemon {
+  /// Browsers kept running between jobs.
+  pool: BrowserPool,
+  /// Time the daemon was started.
+  started: Instant,
+  /// Number of files converted since the daemon was started.
+  files: AtomicU64,
+  /// Flag indicating if the daemon was asked to stop.
+  stopping: AtomicBool,
+  /// Flag indicating if messages about received jobs are printed.
+  verbose: bool,
+}
+
+#[cfg(unix)]
+impl Daemon {
+  /// Reads the request from the connection and writes the response.
+  fn handle(&self, stream: UnixStream, socket: &Path) {
+    let mut line = String::new();
+    if BufReader::new(&stream).read_line(&mut line).is_err() {
+      return;
+    }
+    let response = match serde_json::from_str::<Value>(&line) {
+      Ok(request) => self.respond(&request, socket),
+      Err(_) => json!({ "unsupported": "malformed request" }),
+    };
+    let _ = writeln!(&stream, "{}", response);
+  }
+
+  /// Executes the request and returns the response.
+  fn respond(&self, request: &Value, socket: &Path) -> Value {
+    if request.get("version").and_then(Value::as_str) != Some(HTOP_VERSION) {
+      return json!({ "unsupported": format!("daemon version {} differs from client version", HTOP_VERSION) });
+    }
+    match request.get("command").and_then(Value::as_str) {
+      Some("convert") => {
+        let files = request.get("files").and_then(decode_files);
+        let options = request
+          .get("options")
+          .and_then(|options| decode_options(options, &self.pool.options));
+        let (Some(files), Some(options)) = (files, options) else {
+          return json!({ "unsupported": "malformed conversion job" });
+        };
+        if self.verbose {
+          message!("[{HTOP_NAME}] Converting {} file(s)", files.len());
+        }
+        let mut index = 0;
+        let result = html_to_pdf_with(files, options, |options| {
+          index += 1;
+          self.pool.converter(options, index - 1)
+        });
+        match result {
+          Ok(report) => {
+            self.files.fetch_add(report.succeeded() as u64, Ordering::SeqCst);
+            json!({ "report": encode_report(&report) })
+          }
+          Err(reason) => json!({ "error": encode_error(&reason) }),
+        }
+      }
+      Some("status") => json!({
+        "status": {
+          "pid": std::process::id(),
+          "version": HTOP_VERSION,
+          "uptime": self.started.elapsed().as_secs_f64(),
+          "browsers": self.pool.len(),
+          "files": self.files.load(Ordering::SeqCst),
+        }
+      }),
+      Some("stop") => {
+        self.stopping.store(true, Ordering::SeqCst);
+        // wake up the loop accepting connections
+        let _ = UnixStream::connect(socket);
+        json!({ "stopped": true })
+      }
+      _ => json!({ "unsupported": "unknown command" }),
+    }
+  }
+}
+
+/// Browsers kept running by the daemon, all launched with browser options given to the daemon.
+#[cfg(unix)]
+struct BrowserPool {
+  /// Options given to the daemon, used to launch browsers.
+  options: PdfPrintingOptions,
+  /// Running browsers.
+  browsers: Mutex<Vec<Browser>>,
+}
+
+#[cfg(unix)]
+impl BrowserPool {
+  /// Creates an empty pool launching browsers with specified options.
+  fn new(options: &PdfPrintingOptions) -> Self {
+    Self {
+      options: options.clone(),
+      browsers: Mutex::new(vec![]),
+    }
+  }
+
+  /// Returns the number of running browsers.
+  fn len(&self) -> usize {
+    self.browsers.lock().unwrap().len()
+  }
+
+  /// Returns a converter printing in a new tab of the browser with specified index.
+  fn converter(&self, options: &PdfPrintingOptions, index: usize) -> Result<Converter> {
+    let browser = self.browser(index, false)?;
+    Converter::with_browser(browser, options.clone(), true).or_else(|_| {
+      // the browser crashed or the connection to it was closed, so it is replaced with a new one
+      let browser = self.browser(index, true)?;
+      Converter::with_browser(browser, options.clone(), true)
+    })
+  }
+
+  /// Returns the browser with specified index, the browser is launched when not running yet,
+  /// or when `relaunch` flag is set.
+  fn browser(&self, index: usize, relaunch: bool) -> Result<Browser> {
+    let mut browsers = self.browsers.lock().unwrap();
+    match browsers.get_mut(index) {
+      Some(browser) if !relaunch => Ok(browser.clone()),
+      Some(browser) => {
+        *browser = open_browser(&self.options, DAEMON_IDLE_BROWSER_TIMEOUT)?;
+        Ok(browser.clone())
+      }
+      None => {
+        let browser = open_browser(&self.options, DAEMON_IDLE_BROWSER_TIMEOUT)?;
+        browsers.push(browser.clone());
+        Ok(browser)
+      }
+    }
+  }
+}
+
+/// Returns duration in milliseconds.
+fn millis(duration: Option<Duration>) -> Option<u64> {
+  duration.map(|duration| duration.as_millis() as u64)
+}
+
+/// Encodes printing options sent with the conversion job.
+///
+/// The `verbose` flag is not sent, messages about the job are printed by the client.
+/// Browser options are not sent either, the daemon launches browsers with its own options.
+fn encode_options(options: &PdfPrintingOptions) -> Value {
+  json!({
+    "landscape": options.landscape,
+    "print_background": options.print_background,
+    "paper_width": options.paper_width,
+    "paper_height": options.paper_height,
+    "margin_top": options.margin_top,
+    "margin_bottom": options.margin_bottom,
+    "margin_left": options.margin_left,
+    "margin_right": options.margin_right,
+    "scale": options.scale,
+    "fit_width": options.fit_width,
+    "prefer_css_page_size": options.prefer_css_page_size,
+    "page_ranges": options.page_ranges,
+    "ignore_invalid_page_ranges": options.ignore_invalid_page_ranges,
+    "header_template": options.header_template,
+    "footer_template": options.footer_template,
+    "waits": options.waits.iter().map(encode_wait).collect::<Vec<Value>>(),
+    "jobs": options.jobs,
+    "browsers": options.browsers,
+    "keep_going": options.keep_going,
+    "page_timeout": millis(options.page_timeout),
+    "total_timeout": millis(options.total_timeout),
+    "outline_levels": options.outline_levels.as_ref().map(|levels| [levels.start(), levels.end()]),
+    "title": options.metadata.title,
+    "author": options.metadata.author,
+    "subject": options.metadata.subject,
+    "keywords": options.metadata.keywords,
+    "no_metadata": options.no_metadata,
+  })
+}
+
+/// Encodes the wait condition.
+fn encode_wait(wait: &Wait) -> Value {
+  let (condition, value) = match &wait.condition {
+    WaitCondition::Selector(selector) => ("selector", json!(selector)),
+    WaitCondition::Expression(expression) => ("expression", json!(expression)),
+    WaitCondition::Fonts => ("fonts", Value::Null),
+    WaitCondition::NetworkIdle(idle) => ("network-idle", json!(millis(Some(*idle)))),
+    WaitCondition::Delay(delay) => ("delay", json!(millis(Some(*delay)))),
+  };
+  json!({ "condition": condition, "value": value, "timeout": millis(Some(wait.timeout)) })
+}
+
+/// Decodes files of the conversion job.
+#[cfg(unix)]
+fn decode_files(value: &Value) -> Option<Files> {
+  value
+    .as_array()?
+    .iter()
+    .map(|file| {
+      let [input_url, output_file_name] = file.as_array()?.as_slice() else {
+        return None;
+      };
+      Some((input_url.as_str()?.to_string(), output_file_name.as_str()?.to_string()))
+    })
+    .collect()
+}
+
+/// Decodes printing options of the conversion job, `None` when any option is malformed.
+///
+/// Browser options are taken from options given to the daemon, and the number
+/// of browsers is limited to the number of browsers of the daemon.
+#[cfg(unix)]
+fn decode_options(value: &Value, daemon_options: &PdfPrintingOptions) -> Option<PdfPrintingOptions> {
+  let flag = |name: &str| value.get(name)?.as_bool();
+  let number = |name: &str| value.get(name)?.as_f64();
+  let count = |name: &str| usize::try_from(value.get(name)?.as_u64()?).ok();
+  let optional = |name: &str| value.get(name).filter(|value| !value.is_null());
+  let optional_number = |name: &str| match optional(name) {
+    Some(value) => value.as_f64().map(Some),
+    None => Some(None),
+  };
+  let optional_text = |name: &str| match optional(name) {
+    Some(value) => value.as_str().map(|text| Some(text.to_string())),
+    None => Some(None),
+  };
+  let optional_millis = |name: &str| match optional(name) {
+    Some(value) => value.as_u64().map(|millis| Some(Duration::from_millis(millis))),
+    None => Some(None),
+  };
+  let pair = |name: &str| match optional(name) {
+    Some(value) => match value.as_array()?.as_slice() {
+      [first, second] => Some(Some((first.as_u64()?, second.as_u64()?))),
+      _ => None,
+    },
+    None => Some(None),
+  };
+  let outline_levels = match pair("outline_levels")? {
+    Some((first, last)) => Some(u8::try_from(first).ok()?..=u8::try_from(last).ok()?),
+    None => None,
+  };
+  Some(PdfPrintingOptions {
+    landscape: flag("landscape")?,
+    print_background: flag("print_background")?,
+    paper_width: number("paper_width")?,
+    paper_height: number("paper_height")?,
+    margin_top: optional_number("margin_top")?,
+    margin_bottom: optional_number("margin_bottom")?,
+    margin_left: optional_number("margin_left")?,
+    margin_right: optional_number("margin_right")?,
+    scale: optional_number("scale")?,
+    fit_width: flag("fit_width")?,
+    prefer_css_page_size: flag("prefer_css_page_size")?,
+    page_ranges: optional_text("page_ranges")?,
+    ignore_invalid_page_ranges: flag("ignore_invalid_page_ranges")?,
+    header_template: optional_text("header_template")?,
+    footer_template: optional_text("footer_template")?,
+    waits: value
+      .get("waits")?
+      .as_array()?
+      .iter()
+      .map(decode_wait)
+      .collect::<Option<_>>()?,
+    verbose: false,
+    no_crash_reports: daemon_options.no_crash_reports,
+    chrome_path: daemon_options.chrome_path.clone(),
+    chrome_args: daemon_options.chrome_args.clone(),
+    user_data_dir: daemon_options.user_data_dir.clone(),
+    no_sandbox: daemon_options.no_sandbox,
+    proxy_server: daemon_options.proxy_server.clone(),
+    window_size: daemon_options.window_size,
+    browser_url: daemon_options.browser_url.clone(),
+    browser_port: daemon_options.browser_port,
+    jobs: count("jobs")?,
+    browsers: count("browsers")?.min(daemon_options.browsers.max(1)),
+    keep_going: flag("keep_going")?,
+    page_timeout: optional_millis("page_timeout")?,
+    total_timeout: optional_millis("total_timeout")?,
+    outline_levels,
+    metadata: Metadata {
+      title: optional_text("title")?,
+      author: optional_text("author")?,
+      subject: optional_text("subject")?,
+      keywords: optional_text("keywords")?,
+    },
+    no_metadata: flag("no_metadata")?,
+  })
+}
+
+/// Decodes the wait condition.
+#[cfg(unix)]
+fn decode_wait(value: &Value) -> Option<Wait> {
+  let text = || Some(value.get("value")?.as_str()?.to_string());
+  let duration = || Some(Duration::from_millis(value.get("value")?.as_u64()?));
+  let condition = match value.get("condition")?.as_str()? {
+    "selector" => WaitCondition::Selector(text()?),
+    "expression" => WaitCondition::Expression(text()?),
+    "fonts" => WaitCondition::Fonts,
+    "network-idle" => WaitCondition::NetworkIdle(duration()?),
+    "delay" => WaitCondition::Delay(duration()?),
+    _ => return None,
+  };
+  Some(Wait::new(
+    condition,
+    Duration::from_millis(value.get("timeout")?.as_u64()?),
+  ))
+}
+
+/// Encodes the error, keeping its exit code.
+#[cfg(unix)]
+fn encode_error(reason: &HtopError) -> Value {
+  json!({ "message": reason.to_string(), "exit_code": reason.exit_code() })
+}
+
+/// Decodes the error reported by the daemon.
+fn decode_error(value: &Value) -> HtopError {
+  let message = value.get("message").and_then(Value::as_str).unwrap_or("daemon failed");
+  let exit_code = value
+    .get("exit_code")
+    .and_then(Value::as_u64)
+    .and_then(|exit_code| u8::try_from(exit_code).ok())
+    .unwrap_or(1);
+  err_daemon(message, exit_code)
+}
+
+/// Encodes the report of converted files.
+#[cfg(unix)]
+fn encode_report(report: &BatchReport) -> Value {
+  let files: Vec<Value> = report
+    .files
+    .iter()
+    .map(|file| {
+      json!({
+        "input_url": file.input_url,
+        "output_file_name": file.output_file_name,
+        "duration": file.result.as_ref().ok().map(Duration::as_secs_f64),
+        "error": file.result.as_ref().err().map(encode_error),
+      })
+    })
+    .collect();
+  json!({ "files": files, "duration": report.duration.as_secs_f64() })
+}
+
+/// Decodes the report of converted files.
+fn decode_report(value: &Value) -> Option<BatchReport> {
+  let files = value
+    .get("files")?
+    .as_array()?
+    .iter()
+    .map(|file| {
+      let result = match file.get("error").filter(|error| !error.is_null()) {
+        Some(error) => Err(decode_error(error)),
+        None => Ok(Duration::from_secs_f64(file.get("duration")?.as_f64()?)),
+      };
+      Some(FileReport {
+        input_url: file.get("input_url")?.as_str()?.to_string(),
+        output_file_name: file.get("output_file_name")?.as_str()?.to_string(),
+        result,
+      })
+    })
+    .collect::<Option<Vec<FileReport>>>()?;
+  Some(BatchReport {
+    files,
+    duration: Duration::from_secs_f64(value.get("duration")?.as_f64()?),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn convert_timeout_is_bounded_by_total_timeout() {
+    let options = PdfPrintingOptions {
+      page_timeout: Some(Duration::from_secs(10)),
+      total_timeout: Some(Duration::from_secs(60)),
+      ..Default::default()
+    };
+    assert_eq!(
+      convert_timeout(100, &options),
+      Duration::from_secs(60) + CONTROL_TIMEOUT
+    );
+  }
+
+  #[test]
+  fn convert_timeout_is_bounded_by_page_timeout_of_each_job() {
+    let options = PdfPrintingOptions {
+      page_timeout: Some(Duration::from_secs(10)),
+      jobs: 2,
+      ..Default::default()
+    };
+    assert_eq!(convert_timeout(5, &options), Duration::from_secs(30) + CONTROL_TIMEOUT);
+    assert_eq!(convert_timeout(0, &options), Duration::from_secs(10) + CONTROL_TIMEOUT);
+  }
+
+  #[test]
+  fn convert_timeout_is_bounded_by_wait_timeouts() {
+    let options = PdfPrintingOptions {
+      waits: vec![
+        Wait::new(WaitCondition::Fonts, Duration::from_secs(5)),
+        Wait::new(WaitCondition::Delay(Duration::from_secs(2)), Duration::from_secs(3)),
+      ],
+      ..Default::default()
+    };
+    assert_eq!(
+      convert_timeout(1, &options),
+      DEFAULT_WAIT_TIMEOUT + Duration::from_secs(10) + CONTROL_TIMEOUT
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn browser_options_of_jobs_are_ignored() {
+    let client_options = PdfPrintingOptions {
+      landscape: true,
+      chrome_path: Some(PathBuf::from("/tmp/chrome")),
+      chrome_args: vec!["--remote-debugging-address=0.0.0.0".to_string()],
+      no_sandbox: true,
+      browser_url: Some("ws://127.0.0.1:9222".to_string()),
+      browsers: 8,
+      jobs: 8,
+      ..Default::default()
+    };
+    let daemon_options = PdfPrintingOptions {
+      chrome_args: vec!["--lang=en".to_string()],
+      browsers: 2,
+      ..Default::default()
+    };
+    let options = decode_options(&encode_options(&client_options), &daemon_options).unwrap();
+    assert!(options.landscape);
+    assert_eq!(options.jobs, 8);
+    assert_eq!(options.browsers, 2);
+    assert_eq!(options.chrome_path, None);
+    assert_eq!(options.chrome_args, vec!["--lang=en".to_string()]);
+    assert!(!options.no_sandbox);
+    assert_eq!(options.browser_url, None);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn peer_uid_is_the_current_user() {
+    let (stream, _) = UnixStream::pair().unwrap();
+    assert_eq!(peer_uid(&stream), Some(current_uid()));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn socket_dir_is_created_private() {
+    let dir = env::temp_dir().join(format!("{HTOP_NAME}-test-{}", std::process::id()));
+    prepare_socket_dir(&dir.join("test.sock")).unwrap();
+    let mode = fs::metadata(&dir).unwrap().mode();
+    fs::remove_dir(&dir).unwrap();
+    assert_eq!(mode & 0o777, 0o700);
+  }
+
+  #[test]
+  fn missing_daemon_is_not_running() {
+    let socket = env::temp_dir().join(format!("{HTOP_NAME}-missing-{}.sock", std::process::id()));
+    assert_eq!(status(&socket), None);
+    assert!(!stop(&socket));
+  }
+}
@@ -33,9 +33,13 @@
 /// Type alias for a collection of file tuples.
 pub type Files = Vec<(String, String)>;
 
+/// File name denoting standard input or standard output.
+pub const STDIO: &str = "-";
+
 pub const PDF_EXTENSION: &str = "pdf";
 
-pub const HTML_EXTENSION: &str = "html";
+/// Default extensions of input `HTML` files.
+pub const HTML_EXTENSIONS: [&str; 3] = ["html", "htm", "xhtml"];
 
 pub const HTOP_NAME: &str = env!("CARGO_PKG_NAME");
 
@@ -43,32 +47,271 @@ pub const HTOP_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const HTOP_DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
 
+/// Exit code when some, but not all files were converted in keep-going mode.
+pub const EXIT_SOME_FAILED: u8 = 3;
+
+/// Exit code when no file was converted in keep-going mode.
+pub const EXIT_ALL_FAILED: u8 = 4;
+
+/// Exit code of `daemon stop` and `daemon status` when no daemon is running.
+pub const EXIT_DAEMON_NOT_RUNNING: u8 = 5;
+
+/// Exit code of `daemon` when another daemon is already listening on the socket.
+pub const EXIT_DAEMON_RUNNING: u8 = 6;
+
+pub const HELP_EXIT_CODES: &str = r#"Exit codes:
+   0  all files converted
+   2  invalid command-line arguments
+   3  some files failed in keep-going mode
+   4  all files failed in keep-going mode
+   5  daemon is not running (daemon stop, daemon status)
+   6  daemon is already running (daemon)
+  10  invalid paper format or size
+  11  invalid print scale
+  12  invalid page ranges
+  13  invalid option value
+  14  no input files found
+  20  launching headless chrome failed
+  21  navigating to the page failed
+  22  evaluating a script in the page failed
+  23  printing to PDF failed
+  24  waiting timed out
+  25  processing printed PDF failed
+  26  connecting to running browser failed (--browser-url, --browser-port)
+  30  reading a file or directory failed
+  31  writing a file or directory failed
+  32  canonicalizing a path failed"#;
+
 pub const SUBCOMMAND_SINGLE: &str = "single";
 
 pub const SUBCOMMAND_MULTIPLE: &str = "multiple";
 
+pub const SUBCOMMAND_URLS: &str = "urls";
+
+pub const SUBCOMMAND_MERGE: &str = "merge";
+
+pub const SUBCOMMAND_CONFIG: &str = "config";
+
+pub const SUBCOMMAND_SHOW: &str = "show";
+
+pub const SUBCOMMAND_DAEMON: &str = "daemon";
+
+pub const SUBCOMMAND_STOP: &str = "stop";
+
+pub const SUBCOMMAND_STATUS: &str = "status";
+
 pub const HELP_BACKGROUND: &str = r#"Prints also the backround of the page"#;
 
 pub const HELP_LANDSCAPE: &str = r#"Sets the paper orientation to landscape. In landscape mode,
 the longest paper edge is positioned in horizontal direction"#;
 
-pub const HELP_PAPER: &str = r#"Paper format like A4 (default), A3, A2 and more"#;
+pub const HELP_PAPER: &str = r#"Paper format like A4 (default), A3, Letter, Legal and more.
+Supported are ISO A0-A10, B0-B10, C0-C10, JIS B0-B10,
+US Letter, Legal, Tabloid, Ledger, Executive, Half-Letter,
+ANSI A-E and Arch A-E, names are case-insensitive.
+Custom dimensions are given as WIDTHxHEIGHT with units
+mm, cm, in, pt, px or pc, like 80mmx200mm or 8.5inx11in"#;
+
+pub const HELP_PAPER_WIDTH: &str = r#"Paper width with unit, like 210mm, overrides the width of --paper"#;
+
+pub const HELP_PAPER_HEIGHT: &str = r#"Paper height with unit, like 11in, overrides the height of --paper"#;
+
+pub const HELP_CSS_PAGE_SIZE: &str = r#"Uses the page size declared in CSS @page rules,
+paper size given with --paper is used only when no CSS page size is declared"#;
+
+pub const HELP_MARGIN: &str = r#"Page margins with units in CSS shorthand notation, like 10mm,
+"10mm 15mm" (vertical horizontal), "10mm 15mm 20mm" (top horizontal bottom)
+or "10mm 15mm 20mm 25mm" (top right bottom left)"#;
+
+pub const HELP_MARGIN_TOP: &str = r#"Top margin with unit, like 10mm, overrides --margin"#;
+
+pub const HELP_MARGIN_BOTTOM: &str = r#"Bottom margin with unit, like 10mm, overrides --margin"#;
+
+pub const HELP_MARGIN_LEFT: &str = r#"Left margin with unit, like 10mm, overrides --margin"#;
+
+pub const HELP_MARGIN_RIGHT: &str = r#"Right margin with unit, like 10mm, overrides --margin"#;
+
+pub const HELP_NO_MARGINS: &str = r#"Prints without margins (full-bleed output)"#;
+
+pub const HELP_SCALE: &str = r#"Scale of the page rendering, allowed values are between 0.1 and 2.0"#;
+
+pub const HELP_FIT_WIDTH: &str = r#"Scales wide documents down to fit the printable page width"#;
+
+pub const HELP_PAGES: &str = r#"Pages to print, like "1-3,5,8-", pages are numbered from 1,
+open ranges like 8- and -3 extend to the last and from the first page"#;
+
+pub const HELP_IGNORE_INVALID_PAGES: &str = r#"Silently ignores page ranges beyond the end of the document"#;
+
+pub const HELP_HEADER_TEMPLATE: &str = r#"Page header template given as inline HTML or a path to HTML file.
+Elements with classes date, title, url, pageNumber and totalPages
+are filled in while printing, {{name}} placeholders are replaced
+with HTML-escaped values of variables defined with --var.
+When merging, templates with pageNumber or totalPages make each input
+print twice, so that page numbers continue across inputs"#;
+
+pub const HELP_FOOTER_TEMPLATE: &str = r#"Page footer template given as inline HTML or a path to HTML file,
+supports the same classes and placeholders as --header-template"#;
+
+pub const HELP_VAR: &str = r#"Template variable in the form name=value, may be repeated"#;
+
+pub const HELP_WAIT_FOR_SELECTOR: &str =
+  r#"Waits before printing until an element matching the CSS selector is present"#;
+
+pub const HELP_WAIT_FOR_EXPRESSION: &str = r#"Waits before printing until the JavaScript expression evaluates to true,
+like "window.reportReady === true", returned promises are awaited"#;
+
+pub const HELP_WAIT_FOR_FONTS: &str = r#"Waits before printing until all fonts are loaded (document.fonts.ready)"#;
+
+pub const HELP_WAIT_FOR_NETWORK_IDLE: &str = r#"Waits before printing until no network requests were in flight
+for the given number of milliseconds"#;
+
+pub const HELP_DELAY: &str = r#"Waits the given number of milliseconds before printing,
+after all other wait conditions are satisfied"#;
+
+pub const HELP_WAIT_TIMEOUT: &str = r#"Default timeout in milliseconds of each wait condition"#;
+
+pub const HELP_SELECTOR_TIMEOUT: &str = r#"Timeout in milliseconds of --wait-for-selector, overrides --wait-timeout"#;
+
+pub const HELP_EXPRESSION_TIMEOUT: &str =
+  r#"Timeout in milliseconds of --wait-for-expression, overrides --wait-timeout"#;
+
+pub const HELP_FONTS_TIMEOUT: &str = r#"Timeout in milliseconds of --wait-for-fonts, overrides --wait-timeout"#;
+
+pub const HELP_NETWORK_IDLE_TIMEOUT: &str =
+  r#"Timeout in milliseconds of --wait-for-network-idle, overrides --wait-timeout"#;
+
+pub const HELP_BASE_URL: &str = r#"Base URL resolving relative URLs in HTML read from standard input,
+defaults to the current directory"#;
+
+pub const HELP_TITLE: &str = r#"Document title written into PDF metadata, defaults to the page title"#;
+
+pub const HELP_AUTHOR: &str = r#"Document author written into PDF metadata, defaults to <meta name="author">"#;
+
+pub const HELP_SUBJECT: &str = r#"Document subject written into PDF metadata, defaults to <meta name="description">"#;
+
+pub const HELP_KEYWORDS: &str =
+  r#"Comma-separated keywords written into PDF metadata, defaults to <meta name="keywords">"#;
+
+pub const HELP_NO_METADATA: &str =
+  r#"Keeps PDF metadata as written by the browser, instead of taking it from the page"#;
+
+pub const HELP_OUTLINE: &str = r#"Adds the outline (bookmarks) built from h1-h6 headings to printed documents"#;
+
+pub const HELP_OUTLINE_LEVELS: &str = r#"Heading levels included in the outline, like 2 or 1-3, implies --outline"#;
+
+pub const HELP_PAGE_TIMEOUT: &str = r#"Maximal time in milliseconds of loading, waiting for and printing a single page.
+A timed out page is abandoned and its browser tab is replaced with a new one"#;
+
+pub const HELP_TOTAL_TIMEOUT: &str = r#"Maximal time in milliseconds of converting all files.
+Files not converted in time fail with a timeout"#;
+
+pub const HELP_JOBS: &str = r#"Number of files converted in parallel, each in a separate browser tab.
+A summary with per-file timings is printed when more than one file is converted"#;
+
+pub const HELP_BROWSERS: &str = r#"Number of headless chrome processes the parallel jobs are distributed among"#;
+
+pub const HELP_KEEP_GOING: &str = r#"Continues converting remaining files after a failure,
+failures are listed in the summary printed at the end. Exits with code 0
+when all files were converted, 3 when some files failed and 4 when all files failed"#;
 
 pub const HELP_VERBOSE: &str = r#"Display printing process details"#;
 
 pub const HELP_NO_CRASH_REPORTS: &str = r#"Disables crash reporter of headless chrome"#;
 
+pub const HELP_CHROME_PATH: &str = r#"Path of Chrome or Chromium executable, detected automatically by default"#;
+
+pub const HELP_CHROME_ARG: &str = r#"Additional command-line argument of the browser, like --chrome-arg=--disable-gpu,
+may be repeated"#;
+
+pub const HELP_USER_DATA_DIR: &str = r#"Directory of the browser profile, a new temporary directory is used by default.
+Can not be used with more than one browser"#;
+
+pub const HELP_NO_SANDBOX: &str = r#"Runs the browser without the sandbox, often required in containers"#;
+
+pub const HELP_PROXY_SERVER: &str = r#"Proxy server used by the browser, like http://proxy:3128"#;
+
+pub const HELP_WINDOW_SIZE: &str = r#"Width and height of the browser window in pixels, like 1280x800"#;
+
+pub const HELP_BROWSER_URL: &str = r#"DevTools endpoint of already running browser used instead of launching one,
+like ws://127.0.0.1:9222/devtools/browser/<id> or http://127.0.0.1:9222.
+Tabs opened by htop are closed afterwards, but the browser is left running"#;
+
+pub const HELP_BROWSER_PORT: &str = r#"Remote debugging port of already running browser on the local host,
+used instead of launching one"#;
+
+pub const HELP_SOCKET: &str = r#"Unix domain socket of the daemon, defaults to htop.sock in $XDG_RUNTIME_DIR,
+or in the private htop-$UID directory in the temporary directory"#;
+
+pub const HELP_NO_DAEMON: &str = r#"Converts files in this process, even when the daemon is running"#;
+
 pub const HELP_LOG_LEVEL: &str = r#"Logging level, allowed values are
 error, warn, info, debug, trace, off (default"#;
 
+pub const HELP_CONFIG: &str = r#"Configuration file with default options, defaults to htop.toml
+in the working directory when such file exists"#;
+
+pub const HELP_PROFILE: &str = r#"Named profile from the configuration file, defined in [profile.NAME] table.
+Options given on the command line take precedence over the profile,
+and the profile takes precedence over top-level options of the configuration file"#;
+
 pub const HELP_SINGLE: &str = r#"Convert single HTML file to PDF"#;
 
 pub const HELP_MULTIPLE: &str = r#"Convert multiple HTML files to PDF files"#;
 
-pub const HELP_IN_FILE: &str = r#"Input HTML file"#;
+pub const HELP_CONFIG_COMMAND: &str = r#"Inspect the configuration"#;
+
+pub const HELP_CONFIG_SHOW: &str = r#"Print effective options and where their values come from"#;
+
+pub const HELP_DAEMON: &str = r#"Run the daemon keeping browsers running for fast repeated conversions.
+While the daemon is running, single, multiple and urls commands send their files to it.
+Browsers are launched with browser options given to the daemon"#;
+
+pub const HELP_DAEMON_STOP: &str = r#"Stop the running daemon after running conversions are finished"#;
+
+pub const HELP_DAEMON_STATUS: &str = r#"Print the status of the running daemon"#;
+
+pub const HELP_IN_FILE: &str = r#"Input HTML file, glob pattern like reports/*.html, - to read standard input
+or http, https, file or data URL like http://localhost:3000/report"#;
+
+pub const HELP_OUT_FILE: &str = r#"Output PFD file or - to write standard output.
+Defaults to standard output when HTML is read from standard input"#;
+
+pub const HELP_MERGE: &str = r#"Convert multiple HTML files into a single PDF file.
+When header or footer templates contain pageNumber or totalPages, every file is printed twice
+to continue page numbers across files, so merging takes about twice as long"#;
 
-pub const HELP_OUT_FILE: &str = r#"Output PFD file"#;
+pub const HELP_MERGE_OUT_FILE: &str = r#"Output PDF file or - to write standard output"#;
 
-pub const HELP_IN_DIR: &str = r#"Input directory"#;
+pub const HELP_MERGE_INPUT: &str = r#"Input HTML files, directories, glob patterns or URLs,
+merged in the given order"#;
+
+pub const HELP_MANIFEST: &str = r#"File listing inputs to merge in order, one file path or URL per line.
+Relative paths are resolved against the directory of the manifest"#;
+
+pub const HELP_NATURAL_SORT: &str =
+  r#"Merges inputs sorted in natural order, like chapter2.html before chapter10.html"#;
+
+pub const HELP_URLS: &str = r#"Convert web pages listed in a file to PDF files"#;
+
+pub const HELP_URL_LIST: &str = r#"File listing URLs to convert, one per line, or - to read standard input.
+Empty lines and lines starting with # are skipped"#;
+
+pub const HELP_IN_DIR: &str = r#"Input directory or glob pattern like reports/**/*.html"#;
 
 pub const HELP_OUT_DIR: &str = r#"Output directory"#;
+
+pub const HELP_RECURSIVE: &str = r#"Converts files in subdirectories too, recreating the directory structure
+of the input directory under the output directory"#;
+
+pub const HELP_INCLUDE: &str = r#"Converts only files matching the glob pattern, may be repeated.
+Patterns with / are matched against the path relative to the input directory,
+other patterns against the file name"#;
+
+pub const HELP_EXCLUDE: &str = r#"Skips files and directories matching the glob pattern, may be repeated.
+Patterns with / are matched against the path relative to the input directory,
+other patterns against the file name"#;
+
+pub const HELP_EXTENSIONS: &str = r#"Comma separated extensions of input files, compared case-insensitively"#;
+
+pub const HELP_FOLLOW_SYMLINKS: &str = r#"Follows symbolic links to directories when converting recursively,
+symbolic link loops are detected and skipped"#;
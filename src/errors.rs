@@ -30,65 +30,503 @@
 
 //! # Errors
 
-use std::fmt;
-use std::path::Path;
+use crate::defs::EXIT_DAEMON_RUNNING;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fmt, io};
 
 /// Common result type.
 pub type Result<T, E = HtopError> = std::result::Result<T, E>;
 
+/// Type alias for boxed source errors, like the errors reported by headless chrome.
+pub type BoxedError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
 /// Common error definition.
-#[derive(PartialEq, Eq)]
-pub struct HtopError(String);
+pub enum HtopError {
+  /// Invalid paper format or paper size.
+  InvalidPaper(String),
+  /// Invalid print scale.
+  InvalidScale(String),
+  /// Invalid page ranges.
+  InvalidPageRange(String),
+  /// Invalid value of other option, like margins, lengths, patterns or template variables.
+  InvalidOption(String),
+  /// No input files were found.
+  NoInput(String),
+  /// Launching headless chrome or opening a browser tab failed.
+  ChromeLaunch { source: BoxedError },
+  /// Connecting to already running browser failed.
+  BrowserConnect { endpoint: String, source: BoxedError },
+  /// Navigating to the page failed.
+  Navigation { url: String, source: BoxedError },
+  /// Evaluating a script in the page failed, `context` describes the purpose of the script.
+  Script { context: String, source: BoxedError },
+  /// Printing the page to `PDF` failed.
+  Print { source: BoxedError },
+  /// Waiting for a condition timed out.
+  Timeout(String),
+  /// Processing the printed `PDF` document failed.
+  Pdf(String),
+  /// Reading a file or directory failed.
+  Read { path: PathBuf, source: io::Error },
+  /// Writing a file or creating a directory failed.
+  Write { path: PathBuf, source: io::Error },
+  /// Canonicalizing a path failed.
+  Canonicalize { path: PathBuf, source: io::Error },
+  /// Converting in the daemon failed, `exit_code` is the exit code of the error reported by the daemon.
+  Daemon { message: String, exit_code: u8 },
+}
 
 impl fmt::Display for HtopError {
   /// Implements [Display](fmt::Display) trait for [HtopError].
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    write!(f, "{}", self.0)
+    match self {
+      Self::InvalidPaper(message)
+      | Self::InvalidScale(message)
+      | Self::InvalidPageRange(message)
+      | Self::InvalidOption(message)
+      | Self::NoInput(message)
+      | Self::Timeout(message) => write!(f, "{}", message),
+      Self::ChromeLaunch { source } => write!(f, "launching headless chrome failed with reason: {}", source),
+      Self::BrowserConnect { endpoint, source } => {
+        write!(f, "connecting to browser {} failed with reason: {}", endpoint, source)
+      }
+      Self::Navigation { url, source } => write!(f, "navigating to {} failed with reason: {}", url, source),
+      Self::Script { context, source } => write!(f, "{} failed with reason: {}", context, source),
+      Self::Print { source } => write!(f, "printing to PDF failed with reason: {}", source),
+      Self::Pdf(message) => write!(f, "processing PDF failed with reason: {}", message),
+      Self::Read { path, source } => write!(f, "reading {} failed with reason: {}", path.to_string_lossy(), source),
+      Self::Write { path, source } => write!(f, "writing {} failed with reason: {}", path.to_string_lossy(), source),
+      Self::Canonicalize { path, source } => write!(
+        f,
+        "canonicalizing failed for path {} with reason: {}",
+        path.to_string_lossy(),
+        source
+      ),
+      Self::Daemon { message, .. } => write!(f, "{}", message),
+    }
   }
 }
 
 impl fmt::Debug for HtopError {
   /// Implements [Debug](fmt::Debug) trait for [HtopError].
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    write!(f, "{}", self.0)
+    write!(f, "{}", self)
+  }
+}
+
+impl std::error::Error for HtopError {
+  /// Returns the lower-level source of this error, if any.
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::ChromeLaunch { source }
+      | Self::BrowserConnect { source, .. }
+      | Self::Navigation { source, .. }
+      | Self::Script { source, .. }
+      | Self::Print { source } => Some(source.as_ref()),
+      Self::Read { source, .. } | Self::Write { source, .. } | Self::Canonicalize { source, .. } => Some(source),
+      _ => None,
+    }
   }
 }
 
 impl HtopError {
-  /// Creates a new [HtopError] with specified message.
-  pub fn new(message: String) -> Self {
-    Self(message)
+  /// Returns the process exit code reported for this kind of error.
+  pub fn exit_code(&self) -> u8 {
+    match self {
+      Self::InvalidPaper(_) => 10,
+      Self::InvalidScale(_) => 11,
+      Self::InvalidPageRange(_) => 12,
+      Self::InvalidOption(_) => 13,
+      Self::NoInput(_) => 14,
+      Self::ChromeLaunch { .. } => 20,
+      Self::Navigation { .. } => 21,
+      Self::Script { .. } => 22,
+      Self::Print { .. } => 23,
+      Self::Timeout(_) => 24,
+      Self::Pdf(_) => 25,
+      Self::BrowserConnect { .. } => 26,
+      Self::Read { .. } => 30,
+      Self::Write { .. } => 31,
+      Self::Canonicalize { .. } => 32,
+      Self::Daemon { exit_code, .. } => *exit_code,
+    }
   }
 }
 
 /// Creates invalid paper format error.
 pub fn err_invalid_paper_format(format_name: &str) -> HtopError {
-  HtopError::new(format!("invalid paper format '{}'", format_name))
+  HtopError::InvalidPaper(format!("invalid paper format '{}'", format_name))
+}
+
+/// Creates invalid length error.
+pub fn err_invalid_length(value: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "invalid length '{}', expected a number followed by one of units: mm, cm, in, pt, px, pc",
+    value
+  ))
+}
+
+/// Creates invalid margins error.
+pub fn err_invalid_margins(value: &str) -> HtopError {
+  HtopError::InvalidOption(format!("invalid margins '{}', expected one to four lengths", value))
+}
+
+/// Creates an error when paper size exceeds the limits accepted by headless chrome.
+pub fn err_paper_size_out_of_range(width: f64, height: f64, min: f64, max: f64) -> HtopError {
+  HtopError::InvalidPaper(format!(
+    "paper size {:.3}in x {:.3}in is out of range, both dimensions must be between {:.3}in and {:.3}in",
+    width, height, min, max
+  ))
+}
+
+/// Creates invalid print scale error.
+pub fn err_invalid_scale(value: &str, min: f64, max: f64) -> HtopError {
+  HtopError::InvalidScale(format!(
+    "invalid scale '{}', expected a number between {} and {}",
+    value, min, max
+  ))
+}
+
+/// Creates invalid page range error.
+pub fn err_invalid_page_range(range: &str, reason: &str) -> HtopError {
+  HtopError::InvalidPageRange(format!("invalid page range '{}', {}", range, reason))
 }
 
-/// Creates an error with failure reason message from headless chrome.
-pub fn err_headless_chrome(reason: String) -> HtopError {
-  HtopError::new(format!("headless chrome failed with reason: {}", reason))
+/// Creates an error when launching headless chrome or opening a tab fails.
+pub fn err_chrome_launch(source: impl Into<BoxedError>) -> HtopError {
+  HtopError::ChromeLaunch { source: source.into() }
+}
+
+/// Creates an error when connecting to already running browser fails.
+pub fn err_browser_connect(endpoint: &str, reason: impl fmt::Display) -> HtopError {
+  HtopError::BrowserConnect {
+    endpoint: endpoint.to_string(),
+    source: reason.to_string().into(),
+  }
+}
+
+/// Creates invalid browser endpoint URL error.
+pub fn err_invalid_browser_url(url: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "invalid browser URL '{}', expected ws, wss or http URL of DevTools endpoint",
+    url
+  ))
+}
+
+/// Creates an error when navigating to the page fails.
+pub fn err_navigation(url: &str, source: impl Into<BoxedError>) -> HtopError {
+  HtopError::Navigation {
+    url: url.to_string(),
+    source: source.into(),
+  }
+}
+
+/// Creates an error when printing the page to `PDF` fails.
+pub fn err_print(source: impl Into<BoxedError>) -> HtopError {
+  HtopError::Print { source: source.into() }
+}
+
+/// Creates an error when measuring the document width fails.
+pub fn err_document_width(source: impl Into<BoxedError>) -> HtopError {
+  HtopError::Script {
+    context: "measuring document width".to_string(),
+    source: source.into(),
+  }
+}
+
+/// Creates an error when preparing the page for printing fails.
+pub fn err_prepare_page(source: impl Into<BoxedError>) -> HtopError {
+  HtopError::Script {
+    context: "preparing the page for printing".to_string(),
+    source: source.into(),
+  }
+}
+
+/// Creates an error when collecting headings for the outline fails.
+pub fn err_headings(source: impl Into<BoxedError>) -> HtopError {
+  HtopError::Script {
+    context: "collecting headings".to_string(),
+    source: source.into(),
+  }
+}
+
+/// Creates an error when collecting document metadata fails.
+pub fn err_collect_metadata(source: impl Into<BoxedError>) -> HtopError {
+  HtopError::Script {
+    context: "collecting metadata".to_string(),
+    source: source.into(),
+  }
+}
+
+/// Creates an error when outline levels are invalid.
+pub fn err_invalid_outline_levels(value: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "invalid outline levels '{}', expected a level or a range of levels from 1 to 6, like 1-3",
+    value
+  ))
+}
+
+/// Creates invalid browser window size error.
+pub fn err_invalid_window_size(value: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "invalid window size '{}', expected width and height in pixels, like 1280x800",
+    value
+  ))
+}
+
+/// Creates an error when many browsers would share the same profile directory.
+pub fn err_shared_user_data_dir(browsers: usize) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "user data directory can not be shared by {} browsers, use single browser",
+    browsers
+  ))
+}
+
+/// Creates an error when checking a wait condition fails.
+pub fn err_wait(condition: &str, source: impl Into<BoxedError>) -> HtopError {
+  HtopError::Script {
+    context: format!("waiting for {}", condition),
+    source: source.into(),
+  }
+}
+
+/// Creates an error when the printed `PDF` document can not be processed.
+pub fn err_pdf(reason: impl Into<String>) -> HtopError {
+  HtopError::Pdf(reason.into())
+}
+
+/// Creates an error when a merged page printed between blank pages has unexpected number of pages,
+/// so its page numbers can not continue from preceding pages.
+pub fn err_page_numbering(url: &str, expected: usize, printed: usize) -> HtopError {
+  HtopError::Pdf(format!(
+    "continuing page numbers of {} failed, printed {} pages instead of {}",
+    url, printed, expected
+  ))
+}
+
+/// Creates an error when page ranges are given for merged documents.
+pub fn err_merge_page_ranges() -> HtopError {
+  HtopError::InvalidOption("page ranges can not be used when merging documents".to_string())
+}
+
+/// Creates an error when loading, waiting for or printing a page times out.
+pub fn err_page_timeout(url: &str, timeout: Duration) -> HtopError {
+  HtopError::Timeout(format!(
+    "printing page {} timed out after {}ms",
+    url,
+    timeout.as_millis()
+  ))
+}
+
+/// Creates an error when converting all files times out.
+pub fn err_total_timeout(timeout: Duration) -> HtopError {
+  HtopError::Timeout(format!(
+    "printing files timed out after {}ms in total",
+    timeout.as_millis()
+  ))
+}
+
+/// Creates an error when waiting for a condition times out.
+pub fn err_wait_timeout(condition: &str, timeout: Duration) -> HtopError {
+  HtopError::Timeout(format!(
+    "waiting for {} timed out after {}ms",
+    condition,
+    timeout.as_millis()
+  ))
+}
+
+/// Creates an error when reading standard input fails.
+pub fn err_read_stdin(source: io::Error) -> HtopError {
+  HtopError::Read {
+    path: PathBuf::from("standard input"),
+    source,
+  }
+}
+
+/// Creates an error when writing standard output fails.
+pub fn err_write_stdout(source: io::Error) -> HtopError {
+  HtopError::Write {
+    path: PathBuf::from("standard output"),
+    source,
+  }
 }
 
 /// Creates an error with file writing failure reason.
-pub fn err_write_file(file_name: &str, reason: String) -> HtopError {
-  HtopError::new(format!("writing file {} failed with reason: {}", file_name, reason))
+pub fn err_write_file(path: &Path, source: io::Error) -> HtopError {
+  HtopError::Write {
+    path: path.to_path_buf(),
+    source,
+  }
 }
 
-/// Creates an error when canonicalizing a path fails.
-pub fn err_canonicalize(path: &Path, reason: String) -> HtopError {
-  HtopError::new(format!(
-    "canonicalizing failed for path {} with reason: {}",
+/// Creates an error with file reading failure reason.
+pub fn err_read_file(path: &Path, source: io::Error) -> HtopError {
+  HtopError::Read {
+    path: path.to_path_buf(),
+    source,
+  }
+}
+
+/// Creates invalid template variable definition error.
+pub fn err_invalid_template_variable(definition: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "invalid template variable '{}', expected definition in the form name=value",
+    definition
+  ))
+}
+
+/// Creates an error when the template refers to undefined variable.
+pub fn err_undefined_template_variable(name: &str) -> HtopError {
+  HtopError::InvalidOption(format!("undefined template variable '{}'", name))
+}
+
+/// Creates an error with directory reading failure reason.
+pub fn err_read_dir(path: &Path, source: io::Error) -> HtopError {
+  HtopError::Read {
+    path: path.to_path_buf(),
+    source,
+  }
+}
+
+/// Creates invalid glob pattern error.
+pub fn err_invalid_glob(pattern: &str, reason: String) -> HtopError {
+  HtopError::InvalidOption(format!("invalid glob pattern '{}': {}", pattern, reason))
+}
+
+/// Creates an error when glob pattern does not match any file.
+pub fn err_no_matching_files(pattern: &str) -> HtopError {
+  HtopError::NoInput(format!("no files match the pattern '{}'", pattern))
+}
+
+/// Creates an error when a line of URL list is not a supported URL.
+pub fn err_invalid_url(url_list: &str, line: usize, url: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "invalid URL '{}' in {} at line {}, expected http, https, file or data URL",
+    url, url_list, line
+  ))
+}
+
+/// Creates an error when URL list does not contain any URL.
+pub fn err_no_urls(url_list: &str) -> HtopError {
+  HtopError::NoInput(format!("no URLs listed in {}", url_list))
+}
+
+/// Creates an error when there are no documents to merge.
+pub fn err_nothing_to_merge() -> HtopError {
+  HtopError::NoInput("no documents to merge".to_string())
+}
+
+/// Creates an error when glob pattern matches many files but single output file is given.
+pub fn err_ambiguous_output(pattern: &str, count: usize) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "pattern '{}' matches {} files, but single output file was given",
+    pattern, count
+  ))
+}
+
+/// Creates an error when configuration file is not valid.
+pub fn err_invalid_config(path: &Path, reason: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "invalid configuration in {}: {}",
     path.to_string_lossy(),
     reason
   ))
 }
 
-/// Creates an error when retrieving file name fails.
-pub fn err_file_name(path: &Path) -> HtopError {
-  HtopError::new(format!(
-    "retrieving file name for path {} failed",
+/// Creates an error when configuration file sets an unknown option.
+pub fn err_unknown_config_option(path: &Path, name: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "unknown option '{}' in configuration file {}",
+    name,
+    path.to_string_lossy()
+  ))
+}
+
+/// Creates an error when configuration value has unexpected type.
+pub fn err_invalid_config_value(path: &Path, name: &str, expected: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "invalid value of option '{}' in configuration file {}, expected {}",
+    name,
+    path.to_string_lossy(),
+    expected
+  ))
+}
+
+/// Creates an error when selected profile is not defined in configuration file.
+pub fn err_unknown_profile(path: &Path, profile: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "profile '{}' is not defined in configuration file {}",
+    profile,
     path.to_string_lossy()
   ))
 }
+
+/// Creates an error when profile is selected, but there is no configuration file.
+pub fn err_no_config(profile: &str) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "profile '{}' was selected, but no configuration file was found",
+    profile
+  ))
+}
+
+/// Creates an error reported by the daemon, with the exit code of the original error.
+pub fn err_daemon(message: &str, exit_code: u8) -> HtopError {
+  HtopError::Daemon {
+    message: message.to_string(),
+    exit_code,
+  }
+}
+
+/// Creates an error when the daemon is already listening on the socket.
+pub fn err_daemon_running(socket: &Path) -> HtopError {
+  HtopError::Daemon {
+    message: format!("daemon is already running, listening on {}", socket.to_string_lossy()),
+    exit_code: EXIT_DAEMON_RUNNING,
+  }
+}
+
+/// Creates an error when the daemon is not supported on the platform.
+pub fn err_daemon_unsupported() -> HtopError {
+  HtopError::InvalidOption("daemon requires Unix domain sockets, not supported on this platform".to_string())
+}
+
+/// Creates an error when binding or accepting connections on the socket fails.
+pub fn err_socket(socket: &Path, source: io::Error) -> HtopError {
+  HtopError::Write {
+    path: socket.to_path_buf(),
+    source,
+  }
+}
+
+/// Creates an error when the directory of the daemon socket is accessible to other users.
+pub fn err_socket_dir(dir: &Path) -> HtopError {
+  HtopError::InvalidOption(format!(
+    "directory {} of the daemon socket must be owned by the current user and not accessible to others",
+    dir.to_string_lossy()
+  ))
+}
+
+/// Creates an error with directory creation failure reason.
+pub fn err_create_dir(path: &Path, source: io::Error) -> HtopError {
+  HtopError::Write {
+    path: path.to_path_buf(),
+    source,
+  }
+}
+
+/// Creates an error when canonicalizing a path fails.
+pub fn err_canonicalize(path: &Path, source: io::Error) -> HtopError {
+  HtopError::Canonicalize {
+    path: path.to_path_buf(),
+    source,
+  }
+}
+
+/// Creates an error when retrieving file name fails.
+pub fn err_file_name(path: &Path) -> HtopError {
+  HtopError::Read {
+    path: path.to_path_buf(),
+    source: io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"),
+  }
+}
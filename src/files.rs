@@ -0,0 +1,380 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Input files collection
+
+use crate::defs::{Files, HTML_EXTENSIONS, HTOP_NAME, PDF_EXTENSION, STDIO};
+use crate::errors::{
+  err_canonicalize, err_invalid_glob, err_invalid_url, err_no_urls, err_read_dir, err_read_file, err_read_stdin,
+  err_write_file, Result,
+};
+use crate::utils::{
+  dir_url, file_name, file_url, has_extension, insert_base_url, is_url, message, replace_ext, url_file_name,
+};
+use glob::{MatchOptions, Pattern};
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, fs, io, process};
+
+/// Options used when matching paths against glob patterns.
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+  case_sensitive: true,
+  require_literal_separator: true,
+  require_literal_leading_dot: false,
+};
+
+/// Options for collecting input files from a directory.
+#[derive(Debug, Clone)]
+pub struct CollectOptions {
+  /// Flag indicating if subdirectories should be searched for input files.
+  pub recursive: bool,
+  /// Flag indicating if symbolic links to directories should be followed.
+  pub follow_symlinks: bool,
+  /// Extensions of input files, compared case-insensitively.
+  pub extensions: Vec<String>,
+  /// Patterns of files to be included, empty = all files are included.
+  pub include: Vec<Pattern>,
+  /// Patterns of files and directories to be excluded.
+  pub exclude: Vec<Pattern>,
+}
+
+impl Default for CollectOptions {
+  /// Returns default options, collecting `.html`, `.htm` and `.xhtml` files from the input directory only.
+  fn default() -> Self {
+    Self {
+      recursive: false,
+      follow_symlinks: false,
+      extensions: HTML_EXTENSIONS.iter().map(|extension| extension.to_string()).collect(),
+      include: vec![],
+      exclude: vec![],
+    }
+  }
+}
+
+impl CollectOptions {
+  /// Returns `true` when the file with specified path, relative to the input directory,
+  /// matches any of include patterns (if any) and none of exclude patterns.
+  fn is_selected(&self, relative_path: &Path) -> bool {
+    (self.include.is_empty() || self.include.iter().any(|pattern| matches(pattern, relative_path)))
+      && !self.is_excluded(relative_path)
+  }
+
+  /// Returns `true` when the file or directory with specified path,
+  /// relative to the input directory, matches any of exclude patterns.
+  fn is_excluded(&self, relative_path: &Path) -> bool {
+    self.exclude.iter().any(|pattern| matches(pattern, relative_path))
+  }
+}
+
+/// Parses glob pattern used to include or exclude input files.
+pub fn parse_pattern(pattern: &str) -> Result<Pattern> {
+  Pattern::new(pattern).map_err(|e| err_invalid_glob(pattern, e.to_string()))
+}
+
+/// Returns `true` when specified input contains glob pattern characters.
+pub fn is_glob(input: &str) -> bool {
+  input.contains(['*', '?', '['])
+}
+
+/// Matches the relative path against the pattern.
+///
+/// Patterns containing a path separator are matched against the whole relative path,
+/// other patterns are matched against the file name only.
+fn matches(pattern: &Pattern, relative_path: &Path) -> bool {
+  if pattern.as_str().contains('/') {
+    pattern.matches_path_with(relative_path, MATCH_OPTIONS)
+  } else {
+    relative_path
+      .file_name()
+      .is_some_and(|name| pattern.matches_with(&name.to_string_lossy(), MATCH_OPTIONS))
+  }
+}
+
+/// Returns the output file name for the input file with specified path relative to the base directory.
+fn output_file_name(input_file: &Path, relative_dir: &Path, output_dir: Option<&Path>) -> Result<String> {
+  Ok(if let Some(output_dir) = output_dir {
+    let output_file_path = output_dir.join(relative_dir).join(file_name(input_file)?);
+    output_file_path.to_string_lossy().to_string()
+  } else {
+    replace_ext(input_file)
+  })
+}
+
+/// Collects `HTML` files from input directory and pairs them with output `PDF` file names.
+///
+/// When output directory is specified, the directory structure relative to
+/// the input directory is recreated under the output directory,
+/// otherwise `PDF` files are placed next to their `HTML` sources.
+pub fn collect_files(input_dir: &Path, output_dir: Option<&Path>, options: &CollectOptions) -> Result<Files> {
+  let mut files = vec![];
+  let mut ancestors = vec![input_dir.canonicalize().map_err(|e| err_canonicalize(input_dir, e))?];
+  collect_dir(
+    input_dir,
+    Path::new(""),
+    output_dir,
+    options,
+    &mut ancestors,
+    &mut files,
+  )?;
+  Ok(files)
+}
+
+/// Collects files from a single directory, descending into subdirectories when requested.
+///
+/// `ancestors` holds canonical paths of all directories on the path from the input directory,
+/// a subdirectory resolving to one of them is a symbolic link loop and is skipped.
+fn collect_dir(
+  dir: &Path,
+  relative_dir: &Path,
+  output_dir: Option<&Path>,
+  options: &CollectOptions,
+  ancestors: &mut Vec<PathBuf>,
+  files: &mut Files,
+) -> Result<()> {
+  let mut entries = fs::read_dir(dir)
+    .map_err(|e| err_read_dir(dir, e))?
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<std::io::Result<Vec<PathBuf>>>()
+    .map_err(|e| err_read_dir(dir, e))?;
+  entries.sort();
+  for entry in entries {
+    let Some(name) = entry.file_name() else { continue };
+    let relative_path = relative_dir.join(name);
+    if entry.is_file() {
+      if has_extension(&entry, &options.extensions) && options.is_selected(&relative_path) {
+        files.push((file_url(&entry)?, output_file_name(&entry, relative_dir, output_dir)?));
+      }
+    } else if entry.is_dir() && options.recursive {
+      if (entry.is_symlink() && !options.follow_symlinks) || options.is_excluded(&relative_path) {
+        continue;
+      }
+      let canonical = entry.canonicalize().map_err(|e| err_canonicalize(&entry, e))?;
+      if ancestors.contains(&canonical) {
+        message!("[{HTOP_NAME}] Skipping symbolic link loop: {}", entry.to_string_lossy());
+        continue;
+      }
+      ancestors.push(canonical);
+      collect_dir(&entry, &relative_path, output_dir, options, ancestors, files)?;
+      ancestors.pop();
+    }
+  }
+  Ok(())
+}
+
+/// Collects files matching shell-style glob pattern like `reports/**/*.html`
+/// and pairs them with output `PDF` file names.
+///
+/// The base directory of the pattern is the longest leading path without glob characters.
+/// When output directory is specified, the directory structure relative to the base directory
+/// is recreated under the output directory, otherwise `PDF` files are placed next to their sources.
+/// Extensions are not checked, the pattern selects input files explicitly.
+pub fn collect_glob(pattern: &str, output_dir: Option<&Path>, options: &CollectOptions) -> Result<Files> {
+  let base_dir = Path::new(pattern)
+    .components()
+    .take_while(|component| !is_glob(&component.as_os_str().to_string_lossy()))
+    .filter(|component| !matches!(component, Component::CurDir))
+    .collect::<PathBuf>();
+  let paths = glob::glob_with(pattern, MATCH_OPTIONS).map_err(|e| err_invalid_glob(pattern, e.to_string()))?;
+  let mut files = vec![];
+  for path in paths {
+    let path = path.map_err(|e| {
+      let path = e.path().to_path_buf();
+      err_read_dir(&path, e.into())
+    })?;
+    if !path.is_file() {
+      continue;
+    }
+    let relative_path = path.strip_prefix(&base_dir).unwrap_or(&path).to_path_buf();
+    if options.is_selected(&relative_path) {
+      let relative_dir = relative_path.parent().unwrap_or_else(|| Path::new(""));
+      files.push((file_url(&path)?, output_file_name(&path, relative_dir, output_dir)?));
+    }
+  }
+  Ok(files)
+}
+
+/// Collects URLs listed in a file, one URL per line, `-` reads the list from standard input.
+///
+/// Empty lines and lines starting with `#` are skipped. Output file names are derived
+/// from URL paths, repeated names get a numeric suffix, like `report-2.pdf`.
+pub fn collect_url_list(url_list: &str, output_dir: Option<&Path>) -> Result<Files> {
+  let content = if url_list == STDIO {
+    let mut content = String::new();
+    io::stdin().read_to_string(&mut content).map_err(err_read_stdin)?;
+    content
+  } else {
+    fs::read_to_string(url_list).map_err(|e| err_read_file(Path::new(url_list), e))?
+  };
+  let mut files = vec![];
+  let mut used_names = HashSet::new();
+  for (index, line) in content.lines().enumerate() {
+    let url = line.trim();
+    if url.is_empty() || url.starts_with('#') {
+      continue;
+    }
+    if !is_url(url) {
+      return Err(err_invalid_url(url_list, index + 1, url));
+    }
+    let file_name = url_file_name(url);
+    let stem = file_name
+      .strip_suffix(&format!(".{}", PDF_EXTENSION))
+      .unwrap_or(&file_name);
+    let mut unique_name = file_name.clone();
+    let mut counter = 1;
+    while !used_names.insert(unique_name.clone()) {
+      counter += 1;
+      unique_name = format!("{}-{}.{}", stem, counter, PDF_EXTENSION);
+    }
+    let output_file_name = match output_dir {
+      Some(output_dir) => output_dir.join(unique_name).to_string_lossy().to_string(),
+      None => unique_name,
+    };
+    files.push((url.to_string(), output_file_name));
+  }
+  if files.is_empty() {
+    return Err(err_no_urls(url_list));
+  }
+  Ok(files)
+}
+
+/// Collects URLs of documents to merge, inputs may be URLs, files, directories or glob patterns.
+///
+/// Files in directories and files matching glob patterns follow in alphabetical order.
+pub fn collect_merge_inputs<S: AsRef<str>>(inputs: impl IntoIterator<Item = S>) -> Result<Vec<String>> {
+  let mut urls = vec![];
+  for input in inputs {
+    let input = input.as_ref();
+    if is_url(input) {
+      urls.push(input.to_string());
+    } else if is_glob(input) {
+      urls.extend(
+        collect_glob(input, None, &CollectOptions::default())?
+          .into_iter()
+          .map(|(url, _)| url),
+      );
+    } else if Path::new(input).is_dir() {
+      urls.extend(
+        collect_files(Path::new(input), None, &CollectOptions::default())?
+          .into_iter()
+          .map(|(url, _)| url),
+      );
+    } else {
+      urls.push(file_url(Path::new(input))?);
+    }
+  }
+  Ok(urls)
+}
+
+/// Reads the manifest listing documents to merge, one URL or file path per line.
+///
+/// Empty lines and lines starting with `#` are skipped,
+/// relative file paths are resolved against the directory of the manifest.
+pub fn read_manifest(manifest: &str) -> Result<Vec<String>> {
+  let manifest_path = Path::new(manifest);
+  let content = fs::read_to_string(manifest_path).map_err(|e| err_read_file(manifest_path, e))?;
+  let base_dir = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+  let mut urls = vec![];
+  for line in content.lines() {
+    let input = line.trim();
+    if input.is_empty() || input.starts_with('#') {
+      continue;
+    }
+    if is_url(input) {
+      urls.push(input.to_string());
+    } else {
+      urls.push(file_url(&base_dir.join(input))?);
+    }
+  }
+  Ok(urls)
+}
+
+/// `HTML` document read from standard input, saved in a temporary file
+/// which is removed when dropped.
+pub struct StdinFile {
+  /// Path of the temporary file.
+  path: PathBuf,
+}
+
+impl StdinFile {
+  /// Reads `HTML` document from standard input and saves it in a temporary file.
+  ///
+  /// Relative URLs in the document are resolved against specified base URL,
+  /// or against the current directory when no base URL is given.
+  pub fn read(base_url: Option<&str>) -> Result<Self> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut html = vec![];
+    io::stdin().read_to_end(&mut html).map_err(err_read_stdin)?;
+    let base_url = match base_url {
+      Some(base_url) => base_url.to_string(),
+      None => dir_url(&env::current_dir().map_err(err_read_stdin)?)?,
+    };
+    loop {
+      let path = env::temp_dir().join(format!(
+        "{}-stdin-{}-{}.html",
+        HTOP_NAME,
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+      ));
+      // never follows nor reuses a file created by someone else, readable only by the owner
+      let mut open_options = OpenOptions::new();
+      open_options.write(true).create_new(true);
+      #[cfg(unix)]
+      open_options.mode(0o600);
+      let mut file = match open_options.open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+        Err(e) => return Err(err_write_file(&path, e)),
+      };
+      // removes the file also when writing fails
+      let stdin_file = Self { path };
+      file
+        .write_all(&insert_base_url(&html, &base_url))
+        .map_err(|e| err_write_file(&stdin_file.path, e))?;
+      return Ok(stdin_file);
+    }
+  }
+
+  /// Returns the file URL of the temporary file.
+  pub fn url(&self) -> Result<String> {
+    file_url(&self.path)
+  }
+}
+
+impl Drop for StdinFile {
+  /// Removes the temporary file.
+  fn drop(&mut self) {
+    let _ = fs::remove_file(&self.path);
+  }
+}
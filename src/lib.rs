@@ -0,0 +1,68 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # HTML to PDF converter
+//!
+//! Library interface of `htop`. The [Converter] owns a headless Chrome browser
+//! and may be used to convert many documents without relaunching the browser.
+//!
+//! ```no_run
+//! use htop::{Converter, Result};
+//!
+//! fn main() -> Result<()> {
+//!   let converter = Converter::builder().landscape(true).print_background(true).build()?;
+//!   converter.convert_file_to_file("report.html", "report.pdf")?;
+//!   let pdf = converter.convert_html_string("<h1>Hello</h1>")?;
+//!   assert!(!pdf.is_empty());
+//!   Ok(())
+//! }
+//! ```
+
+pub mod config;
+pub mod converter;
+pub mod daemon;
+pub mod defs;
+pub mod errors;
+pub mod files;
+pub mod margins;
+pub mod metadata;
+pub mod options;
+pub mod outline;
+pub mod paper;
+pub mod pdf;
+pub mod templates;
+pub mod utils;
+pub mod waits;
+
+pub use converter::{html_to_pdf, BatchReport, Converter, ConverterBuilder, FileReport};
+pub use errors::{HtopError, Result};
+pub use margins::Margins;
+pub use options::PdfPrintingOptions;
+pub use paper::{Paper, PaperSize};
@@ -28,116 +28,732 @@
  * limitations under the License.
  */
 
-use crate::converter::html_to_pdf;
-use crate::defs::*;
-use crate::errors::Result;
-use crate::options::PdfPrintingOptions;
-use crate::paper::Paper;
-use crate::utils::*;
-use clap::{arg, command, ArgAction, ArgMatches};
-use std::path::Path;
-use std::{env, fs};
-
-mod converter;
-mod defs;
-mod errors;
-mod options;
-mod paper;
-mod utils;
+use clap::builder::BoolishValueParser;
+use clap::parser::ValueSource;
+use clap::{arg, command, value_parser, Arg, ArgAction, ArgMatches, Command, Id};
+use glob::Pattern;
+use htop::config::{Config, ConfigOrigin, ConfigSetting, ConfigValue};
+use htop::defs::*;
+use htop::errors::{
+  err_ambiguous_output, err_invalid_config_value, err_merge_page_ranges, err_no_config, err_no_matching_files,
+  err_nothing_to_merge, err_unknown_config_option,
+};
+use htop::files::{
+  collect_files, collect_glob, collect_merge_inputs, collect_url_list, is_glob, parse_pattern, read_manifest,
+  CollectOptions, StdinFile,
+};
+use htop::metadata::Metadata;
+use htop::options::{parse_outline_levels, parse_page_ranges, parse_scale, parse_window_size};
+use htop::outline::ALL_HEADING_LEVELS;
+use htop::paper::parse_length;
+use htop::templates::{load_template, parse_template_variable, render_template, TemplateVariables};
+use htop::utils::*;
+use htop::waits::{Wait, WaitCondition, DEFAULT_WAIT_TIMEOUT};
+use htop::{daemon, html_to_pdf, BatchReport, Converter, Margins, Paper, PdfPrintingOptions, Result};
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 
-/// Returns command-line arguments matches.
+/// Returns the definition of command-line arguments.
 #[rustfmt::skip]
-fn get_matches() -> ArgMatches {
+fn build_command() -> Command {
   command!()
     .name(HTOP_NAME)
-    .arg(arg!(-b --background).help(HELP_BACKGROUND).action(ArgAction::SetTrue).display_order(1))
-    .arg(arg!(-l --landscape).help(HELP_LANDSCAPE).action(ArgAction::SetTrue).display_order(2))
-    .arg(arg!(--paper <FORMAT>).help(HELP_PAPER).action(ArgAction::Set).default_value("A4").default_missing_value("A4").display_order(3))
-    .arg(arg!(-v --verbose).help(HELP_VERBOSE).action(ArgAction::SetTrue).display_order(4))
-    .arg(arg!(--"log-level" <LEVEL>).help(HELP_LOG_LEVEL).action(ArgAction::Set).default_missing_value("off").display_order(5))
-    .arg(arg!(--"no-crash-reports").help(HELP_NO_CRASH_REPORTS).action(ArgAction::SetTrue).display_order(6))
+    .after_help(HELP_EXIT_CODES)
+    .arg(arg!(-b --background).help(HELP_BACKGROUND).env("HTOP_BACKGROUND").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(1))
+    .arg(arg!(-l --landscape).help(HELP_LANDSCAPE).env("HTOP_LANDSCAPE").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(2))
+    .arg(arg!(--paper <FORMAT>).help(HELP_PAPER).env("HTOP_PAPER").action(ArgAction::Set).default_value("A4").default_missing_value("A4").display_order(3))
+    .arg(arg!(--"paper-width" <LENGTH>).help(HELP_PAPER_WIDTH).env("HTOP_PAPER_WIDTH").action(ArgAction::Set).display_order(4))
+    .arg(arg!(--"paper-height" <LENGTH>).help(HELP_PAPER_HEIGHT).env("HTOP_PAPER_HEIGHT").action(ArgAction::Set).display_order(5))
+    .arg(arg!(--"css-page-size").help(HELP_CSS_PAGE_SIZE).env("HTOP_CSS_PAGE_SIZE").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(6))
+    .arg(arg!(--margin <MARGINS>).help(HELP_MARGIN).env("HTOP_MARGIN").action(ArgAction::Set).conflicts_with("no-margins").display_order(7))
+    .arg(arg!(--"margin-top" <LENGTH>).help(HELP_MARGIN_TOP).env("HTOP_MARGIN_TOP").action(ArgAction::Set).conflicts_with("no-margins").display_order(8))
+    .arg(arg!(--"margin-bottom" <LENGTH>).help(HELP_MARGIN_BOTTOM).env("HTOP_MARGIN_BOTTOM").action(ArgAction::Set).conflicts_with("no-margins").display_order(9))
+    .arg(arg!(--"margin-left" <LENGTH>).help(HELP_MARGIN_LEFT).env("HTOP_MARGIN_LEFT").action(ArgAction::Set).conflicts_with("no-margins").display_order(10))
+    .arg(arg!(--"margin-right" <LENGTH>).help(HELP_MARGIN_RIGHT).env("HTOP_MARGIN_RIGHT").action(ArgAction::Set).conflicts_with("no-margins").display_order(11))
+    .arg(arg!(--"no-margins").help(HELP_NO_MARGINS).env("HTOP_NO_MARGINS").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(12))
+    .arg(arg!(--scale <SCALE>).help(HELP_SCALE).env("HTOP_SCALE").action(ArgAction::Set).conflicts_with("fit-width").display_order(13))
+    .arg(arg!(--"fit-width").help(HELP_FIT_WIDTH).env("HTOP_FIT_WIDTH").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(14))
+    .arg(arg!(--pages <RANGES>).help(HELP_PAGES).env("HTOP_PAGES").action(ArgAction::Set).display_order(15))
+    .arg(arg!(--"ignore-invalid-pages").help(HELP_IGNORE_INVALID_PAGES).env("HTOP_IGNORE_INVALID_PAGES").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(16))
+    .arg(arg!(--"header-template" <TEMPLATE>).help(HELP_HEADER_TEMPLATE).env("HTOP_HEADER_TEMPLATE").action(ArgAction::Set).display_order(17))
+    .arg(arg!(--"footer-template" <TEMPLATE>).help(HELP_FOOTER_TEMPLATE).env("HTOP_FOOTER_TEMPLATE").action(ArgAction::Set).display_order(18))
+    .arg(arg!(--var <VARIABLE>).help(HELP_VAR).env("HTOP_VAR").action(ArgAction::Append).display_order(19))
+    .arg(arg!(--"wait-for-selector" <SELECTOR>).help(HELP_WAIT_FOR_SELECTOR).env("HTOP_WAIT_FOR_SELECTOR").action(ArgAction::Set).display_order(20))
+    .arg(arg!(--"wait-for-expression" <EXPRESSION>).help(HELP_WAIT_FOR_EXPRESSION).env("HTOP_WAIT_FOR_EXPRESSION").action(ArgAction::Set).display_order(21))
+    .arg(arg!(--"wait-for-fonts").help(HELP_WAIT_FOR_FONTS).env("HTOP_WAIT_FOR_FONTS").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(22))
+    .arg(arg!(--"wait-for-network-idle" <MS>).help(HELP_WAIT_FOR_NETWORK_IDLE).env("HTOP_WAIT_FOR_NETWORK_IDLE").action(ArgAction::Set).value_parser(value_parser!(u64)).display_order(23))
+    .arg(arg!(--delay <MS>).help(HELP_DELAY).env("HTOP_DELAY").action(ArgAction::Set).value_parser(value_parser!(u64)).display_order(24))
+    .arg(arg!(--"wait-timeout" <MS>).help(HELP_WAIT_TIMEOUT).env("HTOP_WAIT_TIMEOUT").action(ArgAction::Set).value_parser(value_parser!(u64)).default_value(DEFAULT_WAIT_TIMEOUT.as_millis().to_string()).display_order(25))
+    .arg(arg!(--"selector-timeout" <MS>).help(HELP_SELECTOR_TIMEOUT).env("HTOP_SELECTOR_TIMEOUT").action(ArgAction::Set).value_parser(value_parser!(u64)).requires("wait-for-selector").display_order(26))
+    .arg(arg!(--"expression-timeout" <MS>).help(HELP_EXPRESSION_TIMEOUT).env("HTOP_EXPRESSION_TIMEOUT").action(ArgAction::Set).value_parser(value_parser!(u64)).requires("wait-for-expression").display_order(27))
+    .arg(arg!(--"fonts-timeout" <MS>).help(HELP_FONTS_TIMEOUT).env("HTOP_FONTS_TIMEOUT").action(ArgAction::Set).value_parser(value_parser!(u64)).requires("wait-for-fonts").display_order(28))
+    .arg(arg!(--"network-idle-timeout" <MS>).help(HELP_NETWORK_IDLE_TIMEOUT).env("HTOP_NETWORK_IDLE_TIMEOUT").action(ArgAction::Set).value_parser(value_parser!(u64)).requires("wait-for-network-idle").display_order(29))
+    .arg(arg!(--title <TITLE>).help(HELP_TITLE).env("HTOP_TITLE").action(ArgAction::Set).conflicts_with("no-metadata").display_order(30))
+    .arg(arg!(--author <AUTHOR>).help(HELP_AUTHOR).env("HTOP_AUTHOR").action(ArgAction::Set).conflicts_with("no-metadata").display_order(31))
+    .arg(arg!(--subject <SUBJECT>).help(HELP_SUBJECT).env("HTOP_SUBJECT").action(ArgAction::Set).conflicts_with("no-metadata").display_order(32))
+    .arg(arg!(--keywords <KEYWORDS>).help(HELP_KEYWORDS).env("HTOP_KEYWORDS").action(ArgAction::Set).conflicts_with("no-metadata").display_order(33))
+    .arg(arg!(--"no-metadata").help(HELP_NO_METADATA).env("HTOP_NO_METADATA").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(34))
+    .arg(arg!(--outline).help(HELP_OUTLINE).env("HTOP_OUTLINE").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(35))
+    .arg(arg!(--"outline-levels" <LEVELS>).help(HELP_OUTLINE_LEVELS).env("HTOP_OUTLINE_LEVELS").action(ArgAction::Set).display_order(36))
+    .arg(arg!(--"base-url" <URL>).help(HELP_BASE_URL).env("HTOP_BASE_URL").action(ArgAction::Set).display_order(37))
+    .arg(arg!(--"page-timeout" <MS>).help(HELP_PAGE_TIMEOUT).env("HTOP_PAGE_TIMEOUT").action(ArgAction::Set).value_parser(value_parser!(u64)).display_order(38))
+    .arg(arg!(--"total-timeout" <MS>).help(HELP_TOTAL_TIMEOUT).env("HTOP_TOTAL_TIMEOUT").action(ArgAction::Set).value_parser(value_parser!(u64)).display_order(39))
+    .arg(arg!(-j --jobs <N>).help(HELP_JOBS).env("HTOP_JOBS").action(ArgAction::Set).value_parser(value_parser!(usize)).default_value("1").display_order(40))
+    .arg(arg!(--browsers <N>).help(HELP_BROWSERS).env("HTOP_BROWSERS").action(ArgAction::Set).value_parser(value_parser!(usize)).default_value("1").display_order(41))
+    .arg(arg!(--"chrome-path" <PATH>).help(HELP_CHROME_PATH).env("HTOP_CHROME_PATH").action(ArgAction::Set).display_order(42))
+    .arg(arg!(--"chrome-arg" <ARG>).help(HELP_CHROME_ARG).env("HTOP_CHROME_ARG").action(ArgAction::Append).allow_hyphen_values(true).display_order(43))
+    .arg(arg!(--"user-data-dir" <DIR>).help(HELP_USER_DATA_DIR).env("HTOP_USER_DATA_DIR").action(ArgAction::Set).display_order(44))
+    .arg(arg!(--"no-sandbox").help(HELP_NO_SANDBOX).env("HTOP_NO_SANDBOX").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(45))
+    .arg(arg!(--"proxy-server" <URL>).help(HELP_PROXY_SERVER).env("HTOP_PROXY_SERVER").action(ArgAction::Set).display_order(46))
+    .arg(arg!(--"window-size" <SIZE>).help(HELP_WINDOW_SIZE).env("HTOP_WINDOW_SIZE").action(ArgAction::Set).display_order(47))
+    .arg(arg!(--"browser-url" <URL>).help(HELP_BROWSER_URL).env("HTOP_BROWSER_URL").action(ArgAction::Set).conflicts_with("browser-port").conflicts_with_all(["chrome-path", "chrome-arg", "user-data-dir", "no-sandbox", "proxy-server", "window-size", "no-crash-reports"]).display_order(48))
+    .arg(arg!(--"browser-port" <PORT>).help(HELP_BROWSER_PORT).env("HTOP_BROWSER_PORT").action(ArgAction::Set).value_parser(value_parser!(u16)).conflicts_with_all(["chrome-path", "chrome-arg", "user-data-dir", "no-sandbox", "proxy-server", "window-size", "no-crash-reports"]).display_order(49))
+    .arg(arg!(--socket <PATH>).help(HELP_SOCKET).env("HTOP_SOCKET").action(ArgAction::Set).display_order(50))
+    .arg(arg!(--"no-daemon").help(HELP_NO_DAEMON).env("HTOP_NO_DAEMON").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(51))
+    .arg(arg!(-k --"keep-going").help(HELP_KEEP_GOING).env("HTOP_KEEP_GOING").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(52))
+    .arg(arg!(-v --verbose).help(HELP_VERBOSE).env("HTOP_VERBOSE").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(53))
+    .arg(arg!(--"log-level" <LEVEL>).help(HELP_LOG_LEVEL).env("HTOP_LOG_LEVEL").action(ArgAction::Set).default_missing_value("off").display_order(54))
+    .arg(arg!(--"no-crash-reports").help(HELP_NO_CRASH_REPORTS).env("HTOP_NO_CRASH_REPORTS").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).display_order(55))
+    .arg(arg!(--config <FILE>).help(HELP_CONFIG).env("HTOP_CONFIG").action(ArgAction::Set).display_order(56))
+    .arg(arg!(--profile <NAME>).help(HELP_PROFILE).env("HTOP_PROFILE").action(ArgAction::Set).display_order(57))
     .subcommand(command!().name(SUBCOMMAND_SINGLE).about(HELP_SINGLE).display_order(1)
       .arg(arg!(<INPUT_FILE>).help(HELP_IN_FILE).required(true).index(1))
       .arg(arg!([OUTPUT_FILE]).help(HELP_OUT_FILE).required(false).index(2)))
     .subcommand(command!().name(SUBCOMMAND_MULTIPLE).about(HELP_MULTIPLE).display_order(2)
       .arg(arg!(<INPUT_DIR>).help(HELP_IN_DIR).required(true).index(1))
+      .arg(arg!([OUTPUT_DIR]).help(HELP_OUT_DIR).required(false).index(2))
+      .arg(arg!(-r --recursive).help(HELP_RECURSIVE).env("HTOP_RECURSIVE").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()))
+      .arg(arg!(--"follow-symlinks").help(HELP_FOLLOW_SYMLINKS).env("HTOP_FOLLOW_SYMLINKS").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new()).requires("recursive"))
+      .arg(arg!(--include <PATTERN>).help(HELP_INCLUDE).env("HTOP_INCLUDE").action(ArgAction::Append))
+      .arg(arg!(--exclude <PATTERN>).help(HELP_EXCLUDE).env("HTOP_EXCLUDE").action(ArgAction::Append))
+      .arg(arg!(--extensions <EXTENSIONS>).help(HELP_EXTENSIONS).env("HTOP_EXTENSIONS").action(ArgAction::Set).value_delimiter(',').default_value("html,htm,xhtml")))
+    .subcommand(command!().name(SUBCOMMAND_MERGE).about(HELP_MERGE).display_order(3)
+      .arg(arg!(<OUTPUT_FILE>).help(HELP_MERGE_OUT_FILE).required(true).index(1))
+      .arg(arg!([INPUT]...).help(HELP_MERGE_INPUT).index(2))
+      .arg(arg!(--manifest <FILE>).help(HELP_MANIFEST).env("HTOP_MANIFEST").action(ArgAction::Set).conflicts_with("INPUT"))
+      .arg(arg!(--"natural-sort").help(HELP_NATURAL_SORT).env("HTOP_NATURAL_SORT").action(ArgAction::SetTrue).value_parser(BoolishValueParser::new())))
+    .subcommand(command!().name(SUBCOMMAND_URLS).about(HELP_URLS).display_order(4)
+      .arg(arg!(<URL_LIST>).help(HELP_URL_LIST).required(true).index(1))
       .arg(arg!([OUTPUT_DIR]).help(HELP_OUT_DIR).required(false).index(2)))
-    .get_matches()
+    .subcommand(command!().name(SUBCOMMAND_CONFIG).about(HELP_CONFIG_COMMAND).display_order(5).subcommand_required(true)
+      .subcommand(command!().name(SUBCOMMAND_SHOW).about(HELP_CONFIG_SHOW)))
+    .subcommand(command!().name(SUBCOMMAND_DAEMON).about(HELP_DAEMON).display_order(6)
+      .subcommand(command!().name(SUBCOMMAND_STOP).about(HELP_DAEMON_STOP))
+      .subcommand(command!().name(SUBCOMMAND_STATUS).about(HELP_DAEMON_STATUS)))
+}
+
+/// Options applied from the configuration file.
+struct AppliedConfig {
+  /// Path of the configuration file.
+  path: PathBuf,
+  /// Options used as defaults of command-line arguments.
+  settings: Vec<ConfigSetting>,
+}
+
+/// Returns command-line arguments matches.
+///
+/// Options from the configuration file and the selected profile become defaults
+/// of command-line arguments, so explicitly given arguments take precedence.
+/// Options conflicting with explicitly given arguments are skipped,
+/// like `margin` from the configuration file when `--no-margins` is given.
+fn get_matches() -> Result<(ArgMatches, Option<AppliedConfig>)> {
+  let args: Vec<OsString> = env::args_os().collect();
+  // preliminary parsing finds the configuration file, the profile and explicitly given arguments
+  let preliminary = build_command().ignore_errors(true).get_matches_from(&args);
+  let profile = preliminary.get_one::<String>("profile");
+  let Some(path) = preliminary
+    .get_one::<String>("config")
+    .map(PathBuf::from)
+    .or_else(Config::find)
+  else {
+    if let Some(profile) = profile {
+      return Err(err_no_config(profile));
+    }
+    return Ok((build_command().get_matches_from(&args), None));
+  };
+  let config = Config::load(&path)?;
+  let mut command = build_command();
+  let mut settings: Vec<ConfigSetting> = vec![];
+  for setting in config.settings(profile.map(String::as_str))? {
+    let (subcommands, arg) = find_configurable(&command, &setting.name)
+      .map(|(subcommands, arg)| (subcommands, arg.clone()))
+      .ok_or_else(|| err_unknown_config_option(&path, &setting.name))?;
+    // conflicts are resolved within the (sub)command defining the argument
+    let parent = subcommands
+      .iter()
+      .try_fold(&command, |parent, name| parent.find_subcommand(name));
+    let explicit = subcommands
+      .iter()
+      .try_fold(&preliminary, |matches, name| matches.subcommand_matches(name));
+    let is_explicit = |other: &Arg| {
+      explicit.is_some_and(|matches| {
+        matches!(
+          matches.value_source(other.get_id().as_str()),
+          Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        )
+      })
+    };
+    // profile options are applied first, so they win over conflicting top-level options
+    let skipped = parent.is_some_and(|parent| {
+      parent.get_arguments().any(|other| {
+        conflicts(parent, &arg, other)
+          && (is_explicit(other)
+            || settings.iter().any(|applied| {
+              other.get_long() == Some(applied.name.as_str()) && applied.origin != ConfigOrigin::Defaults
+            }))
+      })
+    });
+    if skipped {
+      continue;
+    }
+    let values = match (arg.get_action(), &setting.value, arg.get_value_delimiter()) {
+      (ArgAction::SetTrue, ConfigValue::Boolean(flag), _) => vec![flag.to_string()],
+      (ArgAction::Set | ArgAction::Append, ConfigValue::String(value), _) => vec![value.to_owned()],
+      (ArgAction::Append, ConfigValue::Array(values), _) => values.to_owned(),
+      (ArgAction::Set, ConfigValue::Array(values), Some(delimiter)) => vec![values.join(&delimiter.to_string())],
+      (ArgAction::SetTrue, _, _) => return Err(err_invalid_config_value(&path, &setting.name, "true or false")),
+      (ArgAction::Set, _, _) => return Err(err_invalid_config_value(&path, &setting.name, "string or number")),
+      _ => {
+        return Err(err_invalid_config_value(
+          &path,
+          &setting.name,
+          "string or array of strings",
+        ))
+      }
+    };
+    command = with_default_values(command, &subcommands, arg.get_id().clone(), values);
+    settings.push(setting);
+  }
+  Ok((command.get_matches_from(&args), Some(AppliedConfig { path, settings })))
+}
+
+/// Finds the configurable argument with specified long name in the command and its subcommands.
+/// Returns the names of subcommands leading to the argument, together with the argument.
+fn find_configurable<'a>(command: &'a Command, name: &str) -> Option<(Vec<String>, &'a Arg)> {
+  if NOT_CONFIGURABLE.contains(&name) {
+    return None;
+  }
+  if let Some(arg) = command.get_arguments().find(|arg| arg.get_long() == Some(name)) {
+    return Some((vec![], arg));
+  }
+  command.get_subcommands().find_map(|subcommand| {
+    find_configurable(subcommand, name).map(|(mut subcommands, arg)| {
+      subcommands.insert(0, subcommand.get_name().to_string());
+      (subcommands, arg)
+    })
+  })
+}
+
+/// Sets default values of the argument defined in the command or in one of its subcommands.
+fn with_default_values(command: Command, subcommands: &[String], id: Id, values: Vec<String>) -> Command {
+  match subcommands.split_first() {
+    Some((name, rest)) => command.mut_subcommand(name, |subcommand| with_default_values(subcommand, rest, id, values)),
+    None => command.mut_arg(id, |arg| arg.default_values(values)),
+  }
+}
+
+/// Options that can not be set in the configuration file.
+const NOT_CONFIGURABLE: [&str; 2] = ["config", "profile"];
+
+/// Returns `true` when the arguments conflict with each other.
+fn conflicts(command: &Command, arg: &Arg, other: &Arg) -> bool {
+  let conflicting = |arg: &Arg, other: &Arg| {
+    command
+      .get_arg_conflicts_with(arg)
+      .iter()
+      .any(|conflict| conflict.get_id() == other.get_id())
+  };
+  conflicting(arg, other) || conflicting(other, arg)
+}
+
+/// Prints effective values of options, with the origin of each value.
+fn show_config(matches: &ArgMatches, config: Option<&AppliedConfig>) {
+  match config {
+    Some(config) => println!("# configuration file: {}", config.path.to_string_lossy()),
+    None => println!("# no configuration file"),
+  }
+  if let Some(profile) = matches.get_one::<String>("profile") {
+    println!("# profile: {}", profile);
+  }
+  for arg in build_command().get_arguments() {
+    let id = arg.get_id().as_str();
+    let Some(name) = arg.get_long() else {
+      continue;
+    };
+    if NOT_CONFIGURABLE.contains(&name) {
+      continue;
+    }
+    let Some(source) = matches.value_source(id) else {
+      continue;
+    };
+    let origin = match source {
+      ValueSource::CommandLine => "command line".to_string(),
+      ValueSource::EnvVariable => format!(
+        "environment variable {}",
+        arg.get_env().unwrap_or_default().to_string_lossy()
+      ),
+      _ => config
+        .and_then(|config| config.settings.iter().find(|setting| setting.name == name))
+        .map(|setting| match &setting.origin {
+          ConfigOrigin::Defaults => "configuration file".to_string(),
+          ConfigOrigin::Profile(profile) => format!("profile {}", profile),
+        })
+        .unwrap_or_else(|| "default".to_string()),
+    };
+    let values: Vec<String> = matches
+      .get_raw(id)
+      .unwrap_or_default()
+      .map(|value| toml_value(&value.to_string_lossy()))
+      .collect();
+    let value = match arg.get_action() {
+      ArgAction::SetTrue => matches.get_flag(id).to_string(),
+      ArgAction::Append => format!("[{}]", values.join(", ")),
+      _ => values.join(" "),
+    };
+    println!("{} = {}  # {}", name, value, origin);
+  }
+  // options of subcommands are not parsed here, so show values from the configuration file,
+  // environment variables and defaults
+  for subcommand in build_command().get_subcommands() {
+    let mut header = Some(subcommand.get_name());
+    for arg in subcommand.get_arguments() {
+      let Some(name) = arg.get_long() else {
+        continue;
+      };
+      let env = arg.get_env().and_then(|env| env::var_os(env).map(|value| (env, value)));
+      let setting = config.and_then(|config| config.settings.iter().find(|setting| setting.name == name));
+      let (value, origin) = match (env, setting) {
+        (Some((env, value)), _) => (
+          toml_value(&value.to_string_lossy()),
+          format!("environment variable {}", env.to_string_lossy()),
+        ),
+        (None, Some(setting)) => (
+          config_value(&setting.value),
+          match &setting.origin {
+            ConfigOrigin::Defaults => "configuration file".to_string(),
+            ConfigOrigin::Profile(profile) => format!("profile {}", profile),
+          },
+        ),
+        (None, None) => {
+          let defaults: Vec<String> = arg
+            .get_default_values()
+            .iter()
+            .map(|value| toml_value(&value.to_string_lossy()))
+            .collect();
+          if defaults.is_empty() {
+            continue;
+          }
+          (defaults.join(" "), "default".to_string())
+        }
+      };
+      if let Some(header) = header.take() {
+        println!("# {} options", header);
+      }
+      println!("{} = {}  # {}", name, value, origin);
+    }
+  }
+}
+
+/// Formats the value from the configuration file.
+fn config_value(value: &ConfigValue) -> String {
+  match value {
+    ConfigValue::Boolean(flag) => flag.to_string(),
+    ConfigValue::String(value) => toml_value(value),
+    ConfigValue::Array(values) => format!(
+      "[{}]",
+      values
+        .iter()
+        .map(|value| toml_value(value))
+        .collect::<Vec<_>>()
+        .join(", ")
+    ),
+  }
+}
+
+/// Formats the value of an option like in the configuration file.
+fn toml_value(value: &str) -> String {
+  if value == "true" || value == "false" || (!value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit())) {
+    return value.to_string();
+  }
+  let mut literal = String::from("\"");
+  for ch in value.chars() {
+    match ch {
+      '"' => literal.push_str("\\\""),
+      '\\' => literal.push_str("\\\\"),
+      '\n' => literal.push_str("\\n"),
+      '\r' => literal.push_str("\\r"),
+      '\t' => literal.push_str("\\t"),
+      ch if ch.is_control() => literal.push_str(&format!("\\u{:04X}", ch as u32)),
+      ch => literal.push(ch),
+    }
+  }
+  literal.push('"');
+  literal
+}
+
+/// Converts files in the daemon listening on specified socket when it is running,
+/// otherwise in this process.
+///
+/// The summary of converted files is printed with `--keep-going`, `--verbose`
+/// or when more than one file is converted.
+fn convert(files: Files, options: PdfPrintingOptions, socket: Option<&Path>) -> Result<BatchReport> {
+  let print_summary = options.keep_going || options.verbose || files.len() > 1;
+  let jobs = options.jobs.clamp(1, files.len().max(1));
+  let browsers = options.browsers.clamp(1, jobs);
+  let report = match socket
+    .map(|socket| daemon::convert(socket, &files, &options))
+    .transpose()?
+  {
+    Some(Some(report)) => report,
+    _ => html_to_pdf(files, options)?,
+  };
+  if print_summary {
+    report.print_summary(jobs, browsers);
+  }
+  Ok(report)
 }
 
 /// Main entrypoint of the application.
-fn main() -> Result<()> {
+fn main() -> ExitCode {
+  match run() {
+    Ok(exit_code) => exit_code,
+    Err(reason) => {
+      eprintln!("Error: {}", reason);
+      ExitCode::from(reason.exit_code())
+    }
+  }
+}
+
+/// Runs the application and returns the exit code.
+fn run() -> Result<ExitCode> {
   // get command-line argument matches
-  let matches = get_matches();
+  let (matches, config) = get_matches()?;
 
   // initialize the logger
   init_logger(matches.get_one::<String>("log-level").cloned());
 
+  // print effective options before parsing them, so invalid values can be inspected too
+  if let Some((SUBCOMMAND_CONFIG, _)) = matches.subcommand() {
+    show_config(&matches, config.as_ref());
+    return Ok(ExitCode::SUCCESS);
+  }
+
   // parse options
   let landscape = matches.get_flag("landscape");
   let print_background = matches.get_flag("background");
   let verbose = matches.get_flag("verbose");
   let paper_format = matches.get_one::<String>("paper").unwrap();
-  let paper = Paper::new(paper_format.try_into()?);
+  let mut paper = Paper::try_from(paper_format)?;
+  if let Some(paper_width) = matches.get_one::<String>("paper-width") {
+    paper = Paper::custom(parse_length(paper_width)?, paper.height())?;
+  }
+  if let Some(paper_height) = matches.get_one::<String>("paper-height") {
+    paper = Paper::custom(paper.width(), parse_length(paper_height)?)?;
+  }
+  let prefer_css_page_size = matches.get_flag("css-page-size");
+  let margins = if matches.get_flag("no-margins") {
+    Some(Margins::none())
+  } else if let Some(margin) = matches.get_one::<String>("margin") {
+    Some(Margins::try_from(margin)?)
+  } else {
+    None
+  };
+  let margin_side = |name: &str, shorthand: Option<f64>| -> Result<Option<f64>> {
+    match matches.get_one::<String>(name) {
+      Some(length) => Ok(Some(parse_length(length)?)),
+      None => Ok(shorthand),
+    }
+  };
+  let margin_top = margin_side("margin-top", margins.map(|m| m.top))?;
+  let margin_bottom = margin_side("margin-bottom", margins.map(|m| m.bottom))?;
+  let margin_left = margin_side("margin-left", margins.map(|m| m.left))?;
+  let margin_right = margin_side("margin-right", margins.map(|m| m.right))?;
+  let scale = matches
+    .get_one::<String>("scale")
+    .map(|value| parse_scale(value))
+    .transpose()?;
+  let fit_width = matches.get_flag("fit-width");
+  let page_ranges = matches
+    .get_one::<String>("pages")
+    .map(|value| parse_page_ranges(value))
+    .transpose()?;
+  let ignore_invalid_page_ranges = matches.get_flag("ignore-invalid-pages");
+  let mut template_variables = TemplateVariables::new();
+  for definition in matches.get_many::<String>("var").unwrap_or_default() {
+    let (name, value) = parse_template_variable(definition)?;
+    template_variables.insert(name, value);
+  }
+  let template = |name: &str| -> Result<Option<String>> {
+    match matches.get_one::<String>(name) {
+      Some(value) => Ok(Some(render_template(&load_template(value)?, &template_variables)?)),
+      None => Ok(None),
+    }
+  };
+  let header_template = template("header-template")?;
+  let footer_template = template("footer-template")?;
+  let wait_timeout = *matches.get_one::<u64>("wait-timeout").unwrap();
+  let timeout = |name: &str| Duration::from_millis(*matches.get_one::<u64>(name).unwrap_or(&wait_timeout));
+  let mut waits = vec![];
+  if let Some(selector) = matches.get_one::<String>("wait-for-selector") {
+    let condition = WaitCondition::Selector(selector.to_owned());
+    waits.push(Wait::new(condition, timeout("selector-timeout")));
+  }
+  if let Some(expression) = matches.get_one::<String>("wait-for-expression") {
+    let condition = WaitCondition::Expression(expression.to_owned());
+    waits.push(Wait::new(condition, timeout("expression-timeout")));
+  }
+  if matches.get_flag("wait-for-fonts") {
+    waits.push(Wait::new(WaitCondition::Fonts, timeout("fonts-timeout")));
+  }
+  if let Some(idle) = matches.get_one::<u64>("wait-for-network-idle") {
+    let condition = WaitCondition::NetworkIdle(Duration::from_millis(*idle));
+    waits.push(Wait::new(condition, timeout("network-idle-timeout")));
+  }
+  if let Some(delay) = matches.get_one::<u64>("delay") {
+    let delay = Duration::from_millis(*delay);
+    waits.push(Wait::new(WaitCondition::Delay(delay), delay));
+  }
   let no_crash_reports = matches.get_flag("no-crash-reports");
+  let chrome_path = matches.get_one::<String>("chrome-path").map(PathBuf::from);
+  let chrome_args = matches
+    .get_many::<String>("chrome-arg")
+    .unwrap_or_default()
+    .cloned()
+    .collect();
+  let user_data_dir = matches.get_one::<String>("user-data-dir").map(PathBuf::from);
+  let no_sandbox = matches.get_flag("no-sandbox");
+  let proxy_server = matches.get_one::<String>("proxy-server").cloned();
+  let browser_url = matches.get_one::<String>("browser-url").cloned();
+  let browser_port = matches.get_one::<u16>("browser-port").copied();
+  let window_size = matches
+    .get_one::<String>("window-size")
+    .map(|value| parse_window_size(value))
+    .transpose()?;
+  let jobs = *matches.get_one::<usize>("jobs").unwrap();
+  let browsers = *matches.get_one::<usize>("browsers").unwrap();
+  let keep_going = matches.get_flag("keep-going");
+  let outline_levels = match matches.get_one::<String>("outline-levels") {
+    Some(levels) => Some(parse_outline_levels(levels)?),
+    None if matches.get_flag("outline") => Some(ALL_HEADING_LEVELS),
+    None => None,
+  };
+  let metadata = Metadata {
+    title: matches.get_one::<String>("title").cloned(),
+    author: matches.get_one::<String>("author").cloned(),
+    subject: matches.get_one::<String>("subject").cloned(),
+    keywords: matches.get_one::<String>("keywords").cloned(),
+  };
+  let no_metadata = matches.get_flag("no-metadata");
+  let page_timeout = matches
+    .get_one::<u64>("page-timeout")
+    .map(|timeout| Duration::from_millis(*timeout));
+  let total_timeout = matches
+    .get_one::<u64>("total-timeout")
+    .map(|timeout| Duration::from_millis(*timeout));
   let pdf_printing_options = PdfPrintingOptions {
     landscape,
     print_background,
     paper_width: paper.width(),
     paper_height: paper.height(),
+    prefer_css_page_size,
+    margin_top,
+    margin_bottom,
+    margin_left,
+    margin_right,
+    scale,
+    fit_width,
+    page_ranges,
+    ignore_invalid_page_ranges,
+    header_template,
+    footer_template,
+    waits,
     verbose,
     no_crash_reports,
+    chrome_path,
+    chrome_args,
+    user_data_dir,
+    no_sandbox,
+    proxy_server,
+    window_size,
+    browser_url,
+    browser_port,
+    jobs,
+    browsers,
+    keep_going,
+    page_timeout,
+    total_timeout,
+    outline_levels,
+    metadata,
+    no_metadata,
   };
 
+  // files are sent to the running daemon, unless converting in this process is forced
+  let socket = matches
+    .get_one::<String>("socket")
+    .map(PathBuf::from)
+    .unwrap_or_else(daemon::default_socket_path);
+  let daemon_socket = (!matches.get_flag("no-daemon")).then_some(socket.as_path());
+
   // parse subcommands
+  let report;
   match matches.subcommand() {
     Some((SUBCOMMAND_SINGLE, m)) => {
       // input file name is required
       let input_file = m.get_one::<String>("INPUT_FILE").unwrap();
-      let input_file_path = Path::new(input_file);
-      let input_file_url = file_url(input_file_path)?;
       // output file name is optional
-      let output_file_name = if let Some(output_file) = m.get_one::<String>("OUTPUT_FILE") {
-        output_file.to_owned()
+      let output_file = m.get_one::<String>("OUTPUT_FILE");
+      // HTML from standard input is saved in a temporary file removed after printing
+      let mut stdin_file = None;
+      let files = if input_file == STDIO {
+        let stdin_file = stdin_file.insert(StdinFile::read(
+          matches.get_one::<String>("base-url").map(String::as_str),
+        )?);
+        vec![(stdin_file.url()?, output_file.map_or(STDIO, String::as_str).to_owned())]
+      } else if is_url(input_file) {
+        vec![(
+          input_file.to_owned(),
+          output_file.cloned().unwrap_or_else(|| url_file_name(input_file)),
+        )]
+      } else if is_glob(input_file) {
+        let mut files = collect_glob(input_file, None, &CollectOptions::default())?;
+        match (files.len(), output_file) {
+          (0, _) => return Err(err_no_matching_files(input_file)),
+          (1, Some(output_file)) => files[0].1 = output_file.to_owned(),
+          (count, Some(_)) => return Err(err_ambiguous_output(input_file, count)),
+          _ => {}
+        }
+        files
       } else {
-        replace_ext(input_file_path)
+        let input_file_path = Path::new(input_file);
+        let input_file_url = file_url(input_file_path)?;
+        let output_file_name = if let Some(output_file) = output_file {
+          output_file.to_owned()
+        } else {
+          replace_ext(input_file_path)
+        };
+        vec![(input_file_url, output_file_name)]
       };
+      if files.iter().any(|(_, output_file_name)| output_file_name == STDIO) {
+        // keep standard output clean for PDF bytes
+        messages_to_stderr();
+      }
       // convert files
-      html_to_pdf(vec![(input_file_url, output_file_name)], pdf_printing_options)?;
+      report = convert(files, pdf_printing_options, daemon_socket)?;
     }
     Some((SUBCOMMAND_MULTIPLE, m)) => {
-      let mut files: Files = vec![];
       // input directory name is required
       let input_dir = m.get_one::<String>("INPUT_DIR").unwrap();
       // output directory is optional
-      if let Some(output_dir) = m.get_one::<String>("OUTPUT_DIR") {
-        for path in fs::read_dir(input_dir).unwrap() {
-          let entry = path.unwrap().path();
-          if entry.is_file() && has_html_extension(entry.as_path()) {
-            let input_file_url = file_url(&entry)?;
-            let output_file_path = Path::new(output_dir).join(file_name(entry.as_path())?);
-            let output_file_name = output_file_path.to_string_lossy().to_string();
-            files.push((input_file_url, output_file_name));
-          }
+      let output_dir = m.get_one::<String>("OUTPUT_DIR").map(Path::new);
+      let patterns = |name: &str| -> Result<Vec<Pattern>> {
+        m.get_many::<String>(name)
+          .unwrap_or_default()
+          .map(|pattern| parse_pattern(pattern))
+          .collect()
+      };
+      let collect_options = CollectOptions {
+        recursive: m.get_flag("recursive"),
+        follow_symlinks: m.get_flag("follow-symlinks"),
+        extensions: m
+          .get_many::<String>("extensions")
+          .unwrap_or_default()
+          .cloned()
+          .collect(),
+        include: patterns("include")?,
+        exclude: patterns("exclude")?,
+      };
+      let files = if is_glob(input_dir) {
+        let files = collect_glob(input_dir, output_dir, &collect_options)?;
+        if files.is_empty() {
+          return Err(err_no_matching_files(input_dir));
         }
+        files
       } else {
-        for path in fs::read_dir(input_dir).unwrap() {
-          let entry = path.unwrap().path();
-          if entry.is_file() && has_html_extension(entry.as_path()) {
-            let input_file_url = file_url(&entry)?;
-            let output_file_name = replace_ext(entry.as_path());
-            files.push((input_file_url, output_file_name));
-          }
-        }
+        collect_files(Path::new(input_dir), output_dir, &collect_options)?
+      };
+      // convert files
+      report = convert(files, pdf_printing_options, daemon_socket)?;
+    }
+    Some((SUBCOMMAND_MERGE, m)) => {
+      // output file name is required
+      let output_file = m.get_one::<String>("OUTPUT_FILE").unwrap();
+      // inputs are given as arguments or listed in the manifest
+      let mut urls = match m.get_one::<String>("manifest") {
+        Some(manifest) => read_manifest(manifest)?,
+        None => collect_merge_inputs(m.get_many::<String>("INPUT").unwrap_or_default())?,
+      };
+      if urls.is_empty() {
+        return Err(err_nothing_to_merge());
       }
+      if pdf_printing_options.page_ranges.is_some() {
+        return Err(err_merge_page_ranges());
+      }
+      if m.get_flag("natural-sort") {
+        urls.sort_by(|left, right| natural_cmp(left, right));
+      }
+      if output_file == STDIO {
+        // keep standard output clean for PDF bytes
+        messages_to_stderr();
+      }
+      // merge files
+      Converter::new(pdf_printing_options)?.merge_urls_to_file(&urls, output_file)?;
+      return Ok(ExitCode::SUCCESS);
+    }
+    Some((SUBCOMMAND_URLS, m)) => {
+      // URL list is required
+      let url_list = m.get_one::<String>("URL_LIST").unwrap();
+      // output directory is optional
+      let output_dir = m.get_one::<String>("OUTPUT_DIR").map(Path::new);
+      let files = collect_url_list(url_list, output_dir)?;
       // convert files
-      html_to_pdf(files, pdf_printing_options)?;
+      report = convert(files, pdf_printing_options, daemon_socket)?;
+    }
+    Some((SUBCOMMAND_DAEMON, m)) => {
+      let socket_name = socket.to_string_lossy();
+      return Ok(match m.subcommand() {
+        Some((SUBCOMMAND_STOP, _)) => {
+          if daemon::stop(&socket) {
+            println!("{HTOP_NAME} daemon listening on {} was stopped", socket_name);
+            ExitCode::SUCCESS
+          } else {
+            println!("{HTOP_NAME} daemon is not running on {}", socket_name);
+            ExitCode::from(EXIT_DAEMON_NOT_RUNNING)
+          }
+        }
+        Some((SUBCOMMAND_STATUS, _)) => match daemon::status(&socket) {
+          Some(status) => {
+            println!("{HTOP_NAME} daemon is running, listening on {}", socket_name);
+            println!("  pid:      {}", status.pid);
+            println!("  version:  {}", status.version);
+            println!("  uptime:   {:.0}s", status.uptime.as_secs_f64());
+            println!("  browsers: {}", status.browsers);
+            println!("  files:    {}", status.files);
+            ExitCode::SUCCESS
+          }
+          None => {
+            println!("{HTOP_NAME} daemon is not running on {}", socket_name);
+            ExitCode::from(EXIT_DAEMON_NOT_RUNNING)
+          }
+        },
+        _ => {
+          daemon::serve(&socket, &pdf_printing_options)?;
+          ExitCode::SUCCESS
+        }
+      });
     }
     _ => {
       println!("{HTOP_NAME} {HTOP_VERSION}\n{HTOP_DESCRIPTION}\n");
       println!("{HTOP_NAME}: missing subcommand");
       println!("Try '{HTOP_NAME} --help' for more information.");
+      return Ok(ExitCode::SUCCESS);
     }
   }
-  Ok(())
+  Ok(match (report.succeeded(), report.failed()) {
+    (_, 0) => ExitCode::SUCCESS,
+    (0, _) => ExitCode::from(EXIT_ALL_FAILED),
+    _ => ExitCode::from(EXIT_SOME_FAILED),
+  })
 }
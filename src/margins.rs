@@ -0,0 +1,175 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Page margins
+
+use crate::errors::{err_invalid_margins, HtopError, Result};
+use crate::paper::parse_length;
+
+/// Page margins in inches.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Margins {
+  /// Top margin in inches.
+  pub top: f64,
+  /// Right margin in inches.
+  pub right: f64,
+  /// Bottom margin in inches.
+  pub bottom: f64,
+  /// Left margin in inches.
+  pub left: f64,
+}
+
+impl Margins {
+  /// Creates margins with the same length on all sides.
+  pub fn uniform(length: f64) -> Self {
+    Self {
+      top: length,
+      right: length,
+      bottom: length,
+      left: length,
+    }
+  }
+
+  /// Creates zero margins, used for full-bleed printing.
+  pub fn none() -> Self {
+    Self::uniform(0.0)
+  }
+}
+
+impl TryFrom<&str> for Margins {
+  type Error = HtopError;
+  /// Converts [Margins] from CSS shorthand notation.
+  ///
+  /// Like the CSS `margin` property, one to four lengths are accepted:
+  /// - `10mm` applies to all sides,
+  /// - `10mm 15mm` applies to top/bottom and right/left,
+  /// - `10mm 15mm 20mm` applies to top, right/left and bottom,
+  /// - `10mm 15mm 20mm 25mm` applies to top, right, bottom and left.
+  fn try_from(value: &str) -> Result<Self> {
+    let lengths = value
+      .split(|ch: char| ch.is_whitespace() || ch == ',')
+      .filter(|length| !length.is_empty())
+      .map(parse_length)
+      .collect::<Result<Vec<f64>>>()?;
+    match lengths[..] {
+      [all] => Ok(Self::uniform(all)),
+      [vertical, horizontal] => Ok(Self {
+        top: vertical,
+        right: horizontal,
+        bottom: vertical,
+        left: horizontal,
+      }),
+      [top, horizontal, bottom] => Ok(Self {
+        top,
+        right: horizontal,
+        bottom,
+        left: horizontal,
+      }),
+      [top, right, bottom, left] => Ok(Self {
+        top,
+        right,
+        bottom,
+        left,
+      }),
+      _ => Err(err_invalid_margins(value)),
+    }
+  }
+}
+
+impl TryFrom<&String> for Margins {
+  type Error = HtopError;
+  /// Converts [Margins] from a reference to [String].
+  fn try_from(value: &String) -> Result<Self> {
+    Self::try_from(value.as_str())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Creates margins from lengths given in inches, in CSS order.
+  fn margins(top: f64, right: f64, bottom: f64, left: f64) -> Margins {
+    Margins {
+      top,
+      right,
+      bottom,
+      left,
+    }
+  }
+
+  #[test]
+  fn single_length_applies_to_all_sides() {
+    assert_eq!(Margins::try_from("1in").unwrap(), Margins::uniform(1.0));
+    assert_eq!(Margins::try_from(" 0mm ").unwrap(), Margins::none());
+  }
+
+  #[test]
+  fn two_lengths_apply_to_vertical_and_horizontal_sides() {
+    assert_eq!(Margins::try_from("1in 2in").unwrap(), margins(1.0, 2.0, 1.0, 2.0));
+  }
+
+  #[test]
+  fn three_lengths_apply_to_top_horizontal_and_bottom_sides() {
+    assert_eq!(Margins::try_from("1in 2in 3in").unwrap(), margins(1.0, 2.0, 3.0, 2.0));
+  }
+
+  #[test]
+  fn four_lengths_apply_clockwise_from_top() {
+    assert_eq!(
+      Margins::try_from("1in 2in 3in 4in").unwrap(),
+      margins(1.0, 2.0, 3.0, 4.0)
+    );
+  }
+
+  #[test]
+  fn lengths_may_be_separated_by_commas_and_mixed_units() {
+    assert_eq!(Margins::try_from("1in,72pt, 96px  6pc").unwrap(), Margins::uniform(1.0));
+    assert_eq!(
+      Margins::try_from(&"0.5in,1in".to_string()).unwrap(),
+      margins(0.5, 1.0, 0.5, 1.0)
+    );
+  }
+
+  #[test]
+  fn invalid_margins_are_rejected() {
+    assert!(matches!(Margins::try_from(""), Err(HtopError::InvalidOption(_))));
+    assert!(matches!(Margins::try_from(" , "), Err(HtopError::InvalidOption(_))));
+    assert!(matches!(
+      Margins::try_from("1in 2in 3in 4in 5in"),
+      Err(HtopError::InvalidOption(_))
+    ));
+    assert!(matches!(Margins::try_from("10"), Err(HtopError::InvalidOption(_))));
+    assert!(matches!(
+      Margins::try_from("1in -2in"),
+      Err(HtopError::InvalidOption(_))
+    ));
+  }
+}
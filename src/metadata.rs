@@ -0,0 +1,355 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Document metadata
+//!
+//! Printed documents get the document information dictionary and XMP metadata stream
+//! with the title, author, subject and keywords. Values default to the `<title>`
+//! and `<meta name="author|description|keywords">` elements of the printed page.
+//! With `no_metadata` option, documents are left as printed by headless chrome.
+
+use crate::errors::{err_collect_metadata, Result};
+use crate::options::PdfPrintingOptions;
+use crate::pdf::{as_text, entry, name, text, Dictionary, Document, Object, Stream};
+use headless_chrome::Tab;
+
+/// Script collecting metadata of the document, as JSON object.
+const METADATA_SCRIPT: &str = r#"(() => {
+  const meta = (name) => {
+    const element = document.querySelector('meta[name="' + name + '" i]');
+    return element ? element.content : null;
+  };
+  return JSON.stringify({
+    title: document.title || null,
+    author: meta('author'),
+    subject: meta('description'),
+    keywords: meta('keywords'),
+  });
+})()"#;
+
+/// Metadata of the document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+  /// Document title.
+  pub title: Option<String>,
+  /// Name of the document author.
+  pub author: Option<String>,
+  /// Subject of the document.
+  pub subject: Option<String>,
+  /// Keywords associated with the document, separated with commas.
+  pub keywords: Option<String>,
+}
+
+impl Metadata {
+  /// Returns metadata with values missing in this metadata taken from `defaults`.
+  pub fn or(&self, defaults: Metadata) -> Metadata {
+    Metadata {
+      title: self.title.clone().or(defaults.title),
+      author: self.author.clone().or(defaults.author),
+      subject: self.subject.clone().or(defaults.subject),
+      keywords: self.keywords.clone().or(defaults.keywords),
+    }
+  }
+}
+
+/// Collects metadata from the `<title>` and `<meta>` elements of the document loaded in the tab.
+pub fn collect_metadata(tab: &Tab) -> Result<Metadata> {
+  let json = tab
+    .evaluate(METADATA_SCRIPT, false)
+    .map_err(err_collect_metadata)?
+    .value
+    .and_then(|value| value.as_str().map(str::to_string))
+    .ok_or_else(|| err_collect_metadata("metadata is not a string"))?;
+  let metadata = serde_json::from_str::<serde_json::Value>(&json).map_err(err_collect_metadata)?;
+  let value = |key: &str| {
+    metadata[key]
+      .as_str()
+      .map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "))
+      .filter(|value| !value.is_empty())
+  };
+  Ok(Metadata {
+    title: value("title"),
+    author: value("author"),
+    subject: value("subject"),
+    keywords: value("keywords"),
+  })
+}
+
+/// Returns metadata written into printed document, `None` when the document keeps its metadata.
+///
+/// Values given in options take precedence over values collected from the page with `collect`.
+pub fn document_metadata(
+  options: &PdfPrintingOptions,
+  collect: impl FnOnce() -> Result<Metadata>,
+) -> Result<Option<Metadata>> {
+  if options.no_metadata {
+    return Ok(None);
+  }
+  Ok(Some(options.metadata.or(collect()?)))
+}
+
+/// Writes metadata into the document information dictionary and the XMP metadata stream.
+///
+/// Entries written by headless chrome, like the producer and the creation date, are preserved.
+pub fn set_metadata(document: &mut Document, metadata: &Metadata) -> Result<()> {
+  let info = document.info_mut();
+  for (key, value) in [
+    ("Title", &metadata.title),
+    ("Author", &metadata.author),
+    ("Subject", &metadata.subject),
+    ("Keywords", &metadata.keywords),
+  ] {
+    if let Some(value) = value {
+      info.set(key, text(value));
+    }
+  }
+  let value = |key: &str| entry(info, key).and_then(as_text).filter(|value| !value.is_empty());
+  let xmp = xmp_packet(
+    &Metadata {
+      title: value("Title"),
+      author: value("Author"),
+      subject: value("Subject"),
+      keywords: value("Keywords"),
+    },
+    value("Producer"),
+    value("Creator"),
+    value("CreationDate").and_then(|date| xmp_date(&date)),
+  );
+  let mut dictionary = Dictionary::new();
+  dictionary.set("Type", name("Metadata"));
+  dictionary.set("Subtype", name("XML"));
+  let stream = document.add(Object::Stream(Stream::new(dictionary, xmp.into_bytes())));
+  document.catalog_mut()?.set("Metadata", Object::Reference(stream));
+  document.prune();
+  Ok(())
+}
+
+/// Returns the XMP packet with specified metadata.
+fn xmp_packet(
+  metadata: &Metadata,
+  producer: Option<String>,
+  creator: Option<String>,
+  created: Option<String>,
+) -> String {
+  let mut properties = String::from("<dc:format>application/pdf</dc:format>\n");
+  let alternative = |value: &str| {
+    format!(
+      r#"<rdf:Alt><rdf:li xml:lang="x-default">{}</rdf:li></rdf:Alt>"#,
+      escape_xml(value)
+    )
+  };
+  if let Some(title) = &metadata.title {
+    properties.push_str(&format!("<dc:title>{}</dc:title>\n", alternative(title)));
+  }
+  if let Some(author) = &metadata.author {
+    properties.push_str(&format!(
+      "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n",
+      escape_xml(author)
+    ));
+  }
+  if let Some(subject) = &metadata.subject {
+    properties.push_str(&format!("<dc:description>{}</dc:description>\n", alternative(subject)));
+  }
+  if let Some(keywords) = &metadata.keywords {
+    let items = keywords
+      .split(',')
+      .map(str::trim)
+      .filter(|keyword| !keyword.is_empty())
+      .map(|keyword| format!("<rdf:li>{}</rdf:li>", escape_xml(keyword)))
+      .collect::<String>();
+    properties.push_str(&format!("<dc:subject><rdf:Bag>{}</rdf:Bag></dc:subject>\n", items));
+    properties.push_str(&format!("<pdf:Keywords>{}</pdf:Keywords>\n", escape_xml(keywords)));
+  }
+  if let Some(producer) = producer {
+    properties.push_str(&format!("<pdf:Producer>{}</pdf:Producer>\n", escape_xml(&producer)));
+  }
+  if let Some(creator) = creator {
+    properties.push_str(&format!(
+      "<xmp:CreatorTool>{}</xmp:CreatorTool>\n",
+      escape_xml(&creator)
+    ));
+  }
+  if let Some(created) = created {
+    properties.push_str(&format!("<xmp:CreateDate>{}</xmp:CreateDate>\n", created));
+  }
+  format!(
+    r#"<?xpacket begin="{}" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:pdf="http://ns.adobe.com/pdf/1.3/" xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+{}</rdf:Description>
+</rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#,
+    '\u{FEFF}', properties
+  )
+}
+
+/// Converts `PDF` date like `D:20230405123000+02'00'` into XMP date like `2023-04-05T12:30:00+02:00`.
+fn xmp_date(date: &str) -> Option<String> {
+  let date = date.strip_prefix("D:").unwrap_or(date);
+  let digits = date.chars().take_while(char::is_ascii_digit).collect::<String>();
+  if digits.len() < 4 {
+    return None;
+  }
+  let part = |start: usize, default: &'static str| digits.get(start..start + 2).unwrap_or(default).to_string();
+  let mut xmp = format!(
+    "{}-{}-{}T{}:{}:{}",
+    &digits[..4],
+    part(4, "01"),
+    part(6, "01"),
+    part(8, "00"),
+    part(10, "00"),
+    part(12, "00")
+  );
+  let zone = &date[digits.len()..];
+  match zone.chars().next() {
+    Some('Z') => xmp.push('Z'),
+    Some(sign @ ('+' | '-')) => {
+      let zone_digits = zone[1..].chars().filter(char::is_ascii_digit).collect::<String>();
+      if zone_digits.len() >= 4 {
+        xmp.push_str(&format!("{}{}:{}", sign, &zone_digits[..2], &zone_digits[2..4]));
+      }
+    }
+    _ => {}
+  }
+  Some(xmp)
+}
+
+/// Escapes characters with special meaning in XML.
+fn escape_xml(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::pdf::as_dictionary;
+  use crate::pdf::tests::pdf;
+
+  #[test]
+  fn missing_values_are_taken_from_defaults() {
+    let given = Metadata {
+      title: Some("Given".to_string()),
+      ..Metadata::default()
+    };
+    let page = Metadata {
+      title: Some("Page".to_string()),
+      author: Some("Author".to_string()),
+      ..Metadata::default()
+    };
+    let metadata = given.or(page);
+    assert_eq!(metadata.title.as_deref(), Some("Given"));
+    assert_eq!(metadata.author.as_deref(), Some("Author"));
+    assert_eq!(metadata.subject, None);
+  }
+
+  #[test]
+  fn metadata_is_written_into_information_dictionary_and_xmp() {
+    let objects = [
+      "<< /Type /Catalog /Pages 2 0 R >>",
+      "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+      "<< /Type /Page /Parent 2 0 R >>",
+      "<< /Title (Page) /Producer (Skia/PDF) /CreationDate (D:20230405123000+02'00') >>",
+    ];
+    let mut document = Document::load(&pdf(&objects, "<< /Size 5 /Root 1 0 R /Info 4 0 R >>")).unwrap();
+    let metadata = Metadata {
+      author: Some("Tom & Jerry".to_string()),
+      keywords: Some("html, <pdf>".to_string()),
+      ..Metadata::default()
+    };
+    set_metadata(&mut document, &metadata).unwrap();
+    let info = document.get((4, 0)).and_then(as_dictionary).unwrap();
+    let text = |key: &str| entry(info, key).and_then(as_text);
+    assert_eq!(text("Title").as_deref(), Some("Page"));
+    assert_eq!(text("Author").as_deref(), Some("Tom & Jerry"));
+    assert_eq!(text("Producer").as_deref(), Some("Skia/PDF"));
+    let catalog = document.catalog().unwrap();
+    let Object::Stream(xmp) = document.resolve(entry(catalog, "Metadata").unwrap()) else {
+      panic!("missing XMP metadata stream");
+    };
+    let xmp = String::from_utf8(xmp.content.clone()).unwrap();
+    assert!(xmp.contains(r#"<dc:title><rdf:Alt><rdf:li xml:lang="x-default">Page</rdf:li></rdf:Alt></dc:title>"#));
+    assert!(xmp.contains("<dc:creator><rdf:Seq><rdf:li>Tom &amp; Jerry</rdf:li></rdf:Seq></dc:creator>"));
+    assert!(xmp.contains("<rdf:Bag><rdf:li>html</rdf:li><rdf:li>&lt;pdf&gt;</rdf:li></rdf:Bag>"));
+    assert!(xmp.contains("<xmp:CreateDate>2023-04-05T12:30:00+02:00</xmp:CreateDate>"));
+  }
+
+  #[test]
+  fn page_metadata_is_written_when_no_value_is_given() {
+    let page = Metadata {
+      title: Some("Page title".to_string()),
+      author: Some("Page author".to_string()),
+      subject: Some("Page description".to_string()),
+      keywords: Some("html, pdf".to_string()),
+    };
+    let metadata = document_metadata(&PdfPrintingOptions::default(), || Ok(page.clone())).unwrap();
+    assert_eq!(metadata.as_ref(), Some(&page));
+    let objects = [
+      "<< /Type /Catalog /Pages 2 0 R >>",
+      "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+      "<< /Type /Page /Parent 2 0 R >>",
+    ];
+    let mut document = Document::load(&pdf(&objects, "<< /Size 4 /Root 1 0 R >>")).unwrap();
+    set_metadata(&mut document, &metadata.unwrap()).unwrap();
+    let document = Document::load(&document.save().unwrap()).unwrap();
+    let info = document.resolve(entry(document.trailer(), "Info").unwrap());
+    let text = |key: &str| entry(as_dictionary(info).unwrap(), key).and_then(as_text);
+    assert_eq!(text("Title").as_deref(), Some("Page title"));
+    assert_eq!(text("Author").as_deref(), Some("Page author"));
+    assert_eq!(text("Subject").as_deref(), Some("Page description"));
+    assert_eq!(text("Keywords").as_deref(), Some("html, pdf"));
+  }
+
+  #[test]
+  fn page_metadata_is_not_collected_without_metadata() {
+    let options = PdfPrintingOptions {
+      no_metadata: true,
+      ..PdfPrintingOptions::default()
+    };
+    let metadata = document_metadata(&options, || panic!("metadata collected")).unwrap();
+    assert_eq!(metadata, None);
+  }
+
+  #[test]
+  fn pdf_dates_are_converted_to_xmp_dates() {
+    assert_eq!(xmp_date("D:20230405123000Z").as_deref(), Some("2023-04-05T12:30:00Z"));
+    assert_eq!(xmp_date("D:2023").as_deref(), Some("2023-01-01T00:00:00"));
+    assert_eq!(
+      xmp_date("D:20230405-0530").as_deref(),
+      Some("2023-04-05T00:00:00-05:30")
+    );
+    assert_eq!(xmp_date("D:20"), None);
+  }
+}
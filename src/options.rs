@@ -30,10 +30,30 @@
 
 //! # PDF printing options
 
+use crate::errors::{
+  err_invalid_outline_levels, err_invalid_page_range, err_invalid_scale, err_invalid_window_size, Result,
+};
+use crate::metadata::Metadata;
+use crate::outline::ALL_HEADING_LEVELS;
+use crate::paper::{Paper, PaperSize, PX_PER_INCH};
+use crate::templates::EMPTY_TEMPLATE;
+use crate::waits::Wait;
 use headless_chrome::types::PrintToPdfOptions;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Minimal print scale accepted by headless chrome.
+pub const MIN_SCALE: f64 = 0.1;
+
+/// Maximal print scale accepted by headless chrome.
+pub const MAX_SCALE: f64 = 2.0;
+
+/// Default page margin of headless chrome in inches (1cm).
+pub const DEFAULT_MARGIN: f64 = 0.4;
 
 /// PDF printing options.
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct PdfPrintingOptions {
   /// Paper mode, `true` = landscape, `false` = portrait.
   pub landscape: bool,
@@ -43,32 +63,313 @@ pub struct PdfPrintingOptions {
   pub paper_width: f64,
   /// Paper height in inches.
   pub paper_height: f64,
+  /// Top margin in inches, `None` = default margin of headless chrome.
+  pub margin_top: Option<f64>,
+  /// Bottom margin in inches, `None` = default margin of headless chrome.
+  pub margin_bottom: Option<f64>,
+  /// Left margin in inches, `None` = default margin of headless chrome.
+  pub margin_left: Option<f64>,
+  /// Right margin in inches, `None` = default margin of headless chrome.
+  pub margin_right: Option<f64>,
+  /// Scale of the page rendering, `None` = scale 1.0.
+  pub scale: Option<f64>,
+  /// Flag indicating if the scale should be computed to fit the document into printable width.
+  pub fit_width: bool,
+  /// Flag indicating if page size defined in CSS `@page` rule takes precedence over the paper size.
+  pub prefer_css_page_size: bool,
+  /// Ranges of pages to print, like `1-3,5,8-`, `None` = all pages.
+  pub page_ranges: Option<String>,
+  /// Flag indicating if invalid page ranges should be silently ignored by headless chrome.
+  pub ignore_invalid_page_ranges: bool,
+  /// `HTML` template of the page header, `None` = no header.
+  pub header_template: Option<String>,
+  /// `HTML` template of the page footer, `None` = no footer.
+  pub footer_template: Option<String>,
+  /// Conditions the page must satisfy before printing, checked in order.
+  pub waits: Vec<Wait>,
   /// Flag indicating if printing process should be more _talkative_.
   pub verbose: bool,
   /// Flag indicating if crash reporter should be disabled.
   pub no_crash_reports: bool,
+  /// Path of Chrome or Chromium executable, `None` = detected automatically.
+  pub chrome_path: Option<PathBuf>,
+  /// Additional command-line arguments of the browser, like `--disable-gpu`.
+  pub chrome_args: Vec<String>,
+  /// Directory of the browser profile, `None` = new temporary directory on every launch.
+  pub user_data_dir: Option<PathBuf>,
+  /// Flag indicating if the browser should be run without the sandbox.
+  pub no_sandbox: bool,
+  /// Proxy server used by the browser, like `http://proxy:3128`.
+  pub proxy_server: Option<String>,
+  /// Width and height of the browser window in pixels, `None` = default window size.
+  pub window_size: Option<(u32, u32)>,
+  /// DevTools endpoint of already running browser, like `ws://127.0.0.1:9222/devtools/browser/<id>`,
+  /// `None` = the browser is launched.
+  pub browser_url: Option<String>,
+  /// Remote debugging port of already running browser on the local host, `None` = the browser is launched.
+  pub browser_port: Option<u16>,
+  /// Number of browser tabs converting files in parallel.
+  pub jobs: usize,
+  /// Number of browser processes the tabs are distributed among.
+  pub browsers: usize,
+  /// Flag indicating if remaining files should be converted after a failure.
+  pub keep_going: bool,
+  /// Maximal time of loading, waiting for and printing a single page.
+  pub page_timeout: Option<Duration>,
+  /// Maximal time of converting all files.
+  pub total_timeout: Option<Duration>,
+  /// Heading levels included in the document outline, no outline is added when `None`.
+  pub outline_levels: Option<RangeInclusive<u8>>,
+  /// Metadata written into printed documents, overriding values taken from the page.
+  pub metadata: Metadata,
+  /// Flag indicating if printed documents are kept without metadata taken from the page.
+  pub no_metadata: bool,
 }
 
-impl From<PdfPrintingOptions> for PrintToPdfOptions {
-  /// Converts [PdfPrintingOptions] into [PrintToPdfOptions].
-  fn from(value: PdfPrintingOptions) -> Self {
+impl PdfPrintingOptions {
+  /// Returns `true` when already running browser is used instead of launching a new one.
+  pub fn attaches_browser(&self) -> bool {
+    self.browser_url.is_some() || self.browser_port.is_some()
+  }
+
+  /// Returns the printable width in inches, i.e. the paper width
+  /// in chosen orientation reduced by left and right margins.
+  pub fn printable_width(&self) -> f64 {
+    let paper_width = if self.landscape {
+      self.paper_height
+    } else {
+      self.paper_width
+    };
+    paper_width - self.margin_left.unwrap_or(DEFAULT_MARGIN) - self.margin_right.unwrap_or(DEFAULT_MARGIN)
+  }
+
+  /// Returns the scale that fits the document of specified width (in CSS pixels) into printable width.
+  ///
+  /// Documents narrower than printable width are not enlarged.
+  pub fn fit_width_scale(&self, document_width: f64) -> f64 {
+    if document_width > 0.0 {
+      (self.printable_width() * PX_PER_INCH / document_width).clamp(MIN_SCALE, 1.0)
+    } else {
+      1.0
+    }
+  }
+}
+
+impl Default for PdfPrintingOptions {
+  /// Returns default printing options, portrait `A4` paper without background.
+  fn default() -> Self {
+    let paper = Paper::new(PaperSize::A4);
     Self {
-      landscape: Some(value.landscape),
-      display_header_footer: None,
-      print_background: Some(value.print_background),
-      scale: None,
-      paper_width: Some(value.paper_width),
-      paper_height: Some(value.paper_height),
+      landscape: false,
+      print_background: false,
+      paper_width: paper.width(),
+      paper_height: paper.height(),
       margin_top: None,
       margin_bottom: None,
       margin_left: None,
       margin_right: None,
+      scale: None,
+      fit_width: false,
+      prefer_css_page_size: false,
       page_ranges: None,
-      ignore_invalid_page_ranges: None,
+      ignore_invalid_page_ranges: false,
       header_template: None,
       footer_template: None,
-      prefer_css_page_size: None,
+      waits: vec![],
+      verbose: false,
+      no_crash_reports: false,
+      chrome_path: None,
+      chrome_args: vec![],
+      user_data_dir: None,
+      no_sandbox: false,
+      proxy_server: None,
+      window_size: None,
+      browser_url: None,
+      browser_port: None,
+      jobs: 1,
+      browsers: 1,
+      keep_going: false,
+      page_timeout: None,
+      total_timeout: None,
+      outline_levels: None,
+      metadata: Metadata::default(),
+      no_metadata: false,
+    }
+  }
+}
+
+/// Checks if the scale is within the range accepted by headless chrome.
+pub fn check_scale(scale: f64) -> Result<f64> {
+  if (MIN_SCALE..=MAX_SCALE).contains(&scale) {
+    Ok(scale)
+  } else {
+    Err(err_invalid_scale(&scale.to_string(), MIN_SCALE, MAX_SCALE))
+  }
+}
+
+/// Parses the print scale and checks if it is within the range accepted by headless chrome.
+pub fn parse_scale(value: &str) -> Result<f64> {
+  match value.trim().parse::<f64>() {
+    Ok(scale) if (MIN_SCALE..=MAX_SCALE).contains(&scale) => Ok(scale),
+    _ => Err(err_invalid_scale(value, MIN_SCALE, MAX_SCALE)),
+  }
+}
+
+/// Parses page ranges like `1-3,5,8-` and returns them normalized for headless chrome.
+///
+/// Each comma separated range is either a single page `N`, a closed range `N-M`,
+/// an open range `N-` (up to the last page) or `-M` (from the first page).
+/// Pages are numbered from 1 and the start of a range must not be greater than its end.
+pub fn parse_page_ranges(value: &str) -> Result<String> {
+  let page_number = |range: &str, number: &str| -> Result<Option<u32>> {
+    let number = number.trim();
+    if number.is_empty() {
+      return Ok(None);
+    }
+    match number.parse::<u32>() {
+      Ok(0) => Err(err_invalid_page_range(range, "pages are numbered from 1")),
+      Ok(page) => Ok(Some(page)),
+      Err(_) => Err(err_invalid_page_range(range, "expected page number")),
+    }
+  };
+  let mut ranges = vec![];
+  for range in value.split(',').map(str::trim) {
+    if range.is_empty() {
+      return Err(err_invalid_page_range(value, "contains an empty range"));
+    }
+    let normalized = match range.split_once('-') {
+      None => page_number(range, range)?.map(|page| page.to_string()),
+      Some((start, end)) => match (page_number(range, start)?, page_number(range, end)?) {
+        (Some(start), Some(end)) if start > end => {
+          return Err(err_invalid_page_range(range, "start page is greater than end page"))
+        }
+        (Some(start), Some(end)) => Some(format!("{start}-{end}")),
+        (Some(start), None) => Some(format!("{start}-")),
+        (None, Some(end)) => Some(format!("-{end}")),
+        (None, None) => None,
+      },
+    };
+    ranges.push(normalized.ok_or_else(|| err_invalid_page_range(range, "expected page number"))?);
+  }
+  Ok(ranges.join(","))
+}
+
+/// Parses heading levels included in the outline, given as a single level like `2`
+/// or a range of levels like `1-3`.
+pub fn parse_outline_levels(value: &str) -> Result<RangeInclusive<u8>> {
+  let level = |level: &str| {
+    level
+      .trim()
+      .parse::<u8>()
+      .ok()
+      .filter(|level| ALL_HEADING_LEVELS.contains(level))
+      .ok_or_else(|| err_invalid_outline_levels(value))
+  };
+  let levels = match value.split_once('-') {
+    Some((first, last)) => level(first)?..=level(last)?,
+    None => level(value)?..=level(value)?,
+  };
+  if levels.is_empty() {
+    return Err(err_invalid_outline_levels(value));
+  }
+  Ok(levels)
+}
+
+/// Parses browser window size given as width and height in pixels, like `1280x800`.
+pub fn parse_window_size(value: &str) -> Result<(u32, u32)> {
+  let size = |size: &str| {
+    size
+      .trim()
+      .parse::<u32>()
+      .ok()
+      .filter(|size| *size > 0)
+      .ok_or_else(|| err_invalid_window_size(value))
+  };
+  match value.split_once(['x', 'X', ',']) {
+    Some((width, height)) => Ok((size(width)?, size(height)?)),
+    None => Err(err_invalid_window_size(value)),
+  }
+}
+
+impl From<PdfPrintingOptions> for PrintToPdfOptions {
+  /// Converts [PdfPrintingOptions] into [PrintToPdfOptions].
+  fn from(value: PdfPrintingOptions) -> Self {
+    let display_header_footer = value.header_template.is_some() || value.footer_template.is_some();
+    let (header_template, footer_template) = if display_header_footer {
+      (
+        Some(value.header_template.unwrap_or_else(|| EMPTY_TEMPLATE.to_string())),
+        Some(value.footer_template.unwrap_or_else(|| EMPTY_TEMPLATE.to_string())),
+      )
+    } else {
+      (None, None)
+    };
+    Self {
+      landscape: Some(value.landscape),
+      display_header_footer: Some(display_header_footer),
+      print_background: Some(value.print_background),
+      scale: value.scale,
+      paper_width: Some(value.paper_width),
+      paper_height: Some(value.paper_height),
+      margin_top: value.margin_top,
+      margin_bottom: value.margin_bottom,
+      margin_left: value.margin_left,
+      margin_right: value.margin_right,
+      page_ranges: value.page_ranges,
+      ignore_invalid_page_ranges: Some(value.ignore_invalid_page_ranges),
+      header_template,
+      footer_template,
+      prefer_css_page_size: Some(value.prefer_css_page_size),
       transfer_mode: None,
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::errors::HtopError;
+
+  /// Asserts that parsing page ranges fails with the error mentioning expected reason.
+  fn assert_invalid(value: &str, reason: &str) {
+    match parse_page_ranges(value) {
+      Err(error @ HtopError::InvalidPageRange(_)) => {
+        assert!(error.to_string().contains(reason), "'{}': {}", value, error)
+      }
+      other => panic!("'{}' parsed into {:?}", value, other.map_err(|e| e.to_string())),
+    }
+  }
+
+  #[test]
+  fn page_ranges_are_normalized() {
+    assert_eq!(parse_page_ranges("1-3,5,8-").unwrap(), "1-3,5,8-");
+    assert_eq!(parse_page_ranges(" 1 - 3 , 5 ,-4, 8 - ").unwrap(), "1-3,5,-4,8-");
+    assert_eq!(parse_page_ranges("2-2").unwrap(), "2-2");
+  }
+
+  #[test]
+  fn page_zero_is_rejected() {
+    assert_invalid("0", "pages are numbered from 1");
+    assert_invalid("0-3", "pages are numbered from 1");
+    assert_invalid("1,-0", "pages are numbered from 1");
+  }
+
+  #[test]
+  fn reversed_page_range_is_rejected() {
+    assert_invalid("5-3", "start page is greater than end page");
+  }
+
+  #[test]
+  fn empty_page_ranges_are_rejected() {
+    assert_invalid("", "contains an empty range");
+    assert_invalid("1,,2", "contains an empty range");
+    assert_invalid("1,", "contains an empty range");
+    assert_invalid("-", "expected page number");
+  }
+
+  #[test]
+  fn non_numeric_pages_are_rejected() {
+    for value in ["a", "1-b", "1-2-3", "1.5", "-1-"] {
+      assert_invalid(value, "expected page number");
+    }
+  }
+}
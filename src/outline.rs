@@ -0,0 +1,209 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Document outline
+//!
+//! Outline (bookmarks) of printed documents is built from `h1`–`h6` headings.
+//! Before printing, every heading gets a hidden link pointing to it, so headless chrome
+//! prints a named destination at the heading position. After printing, outline entries
+//! are added to the document, pointing to these destinations.
+
+use crate::errors::{err_headings, Result};
+use crate::pdf::{Document, OutlineEntry};
+use crate::utils::js_string;
+use headless_chrome::Tab;
+use std::ops::RangeInclusive;
+
+/// All heading levels.
+pub const ALL_HEADING_LEVELS: RangeInclusive<u8> = 1..=6;
+
+/// Heading of the document included in the outline.
+#[derive(Debug, Clone)]
+pub struct Heading {
+  /// Heading level, 1 for `h1` up to 6 for `h6`.
+  pub level: u8,
+  /// Heading text.
+  pub title: String,
+  /// Name of the destination printed at the heading position.
+  pub destination: String,
+}
+
+/// Returns the script collecting visible headings of specified levels, as JSON array.
+fn headings_script(levels: &RangeInclusive<u8>) -> String {
+  let selector = levels
+    .clone()
+    .filter(|level| ALL_HEADING_LEVELS.contains(level))
+    .map(|level| format!("h{}", level))
+    .collect::<Vec<_>>()
+    .join(",");
+  let selector = js_string(&selector);
+  format!(
+    r#"(() => {{
+  const headings = [];
+  if (!document.body || !{selector}) return JSON.stringify(headings);
+  const links = document.createElement('div');
+  links.style.display = 'none';
+  let counter = 0;
+  for (const heading of document.querySelectorAll({selector})) {{
+    const title = heading.textContent.replace(/\s+/g, ' ').trim();
+    if (!title || heading.getClientRects().length === 0) continue;
+    if (!heading.id) heading.id = 'htop-heading-' + (++counter);
+    const destination = encodeURIComponent(heading.id);
+    const link = document.createElement('a');
+    link.href = '#' + destination;
+    links.appendChild(link);
+    headings.push({{ level: Number(heading.tagName.substring(1)), title, destination }});
+  }}
+  document.body.appendChild(links);
+  return JSON.stringify(headings);
+}})()"#
+  )
+}
+
+/// Collects visible headings of specified levels from the document loaded in the tab.
+pub fn collect_headings(tab: &Tab, levels: &RangeInclusive<u8>) -> Result<Vec<Heading>> {
+  let json = tab
+    .evaluate(&headings_script(levels), false)
+    .map_err(err_headings)?
+    .value
+    .and_then(|value| value.as_str().map(str::to_string))
+    .ok_or_else(|| err_headings("headings are not a string"))?;
+  let headings = serde_json::from_str::<serde_json::Value>(&json).map_err(err_headings)?;
+  Ok(
+    headings
+      .as_array()
+      .map(Vec::as_slice)
+      .unwrap_or_default()
+      .iter()
+      .filter_map(|heading| {
+        Some(Heading {
+          level: heading["level"].as_u64()? as u8,
+          title: heading["title"].as_str()?.to_string(),
+          destination: heading["destination"].as_str()?.to_string(),
+        })
+      })
+      .collect(),
+  )
+}
+
+/// Adds the outline built from headings to printed `PDF` document.
+///
+/// Headings without a printed destination are skipped.
+pub fn add_outline(document: &mut Document, headings: &[Heading]) -> Result<()> {
+  let destinations = document.named_destinations();
+  let entries = headings
+    .iter()
+    .filter_map(|heading| {
+      Some(OutlineEntry {
+        level: heading.level,
+        title: heading.title.clone(),
+        destination: destinations.get(heading.destination.as_bytes())?.clone(),
+      })
+    })
+    .collect::<Vec<_>>();
+  if entries.is_empty() {
+    return Ok(());
+  }
+  document.set_outline(entries)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::pdf::tests::{pdf, written};
+  use crate::pdf::{as_dictionary, as_text, entry, Object};
+
+  /// Printed document with named destinations `intro` and `scope`.
+  fn printed() -> Document {
+    let objects = [
+      "<< /Type /Catalog /Pages 2 0 R /Dests << /intro [3 0 R /XYZ 0 792 0] >> /Names << /Dests 4 0 R >> >>",
+      "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 612 792] >>",
+      "<< /Type /Page /Parent 2 0 R >>",
+      "<< /Names [(scope) 5 0 R] >>",
+      "<< /D [3 0 R /XYZ 0 400 0] >>",
+    ];
+    Document::load(&pdf(&objects, "<< /Size 6 /Root 1 0 R >>")).unwrap()
+  }
+
+  /// Creates a heading pointing to the destination.
+  fn heading(level: u8, title: &str, destination: &str) -> Heading {
+    Heading {
+      level,
+      title: title.to_string(),
+      destination: destination.to_string(),
+    }
+  }
+
+  /// Returns the titles of top-level outline items and the destination of the first one.
+  fn outline(document: &Document) -> Option<(Vec<String>, Object)> {
+    let catalog = document.catalog().ok()?;
+    let root = as_dictionary(document.resolve(entry(catalog, "Outlines")?))?;
+    let mut titles = vec![];
+    let mut next = entry(root, "First").cloned();
+    let mut first_destination = None;
+    while let Some(Object::Reference(id)) = next {
+      let item = as_dictionary(document.get(id)?)?;
+      titles.push(as_text(entry(item, "Title")?)?);
+      first_destination.get_or_insert_with(|| entry(item, "Dest").cloned().unwrap_or(Object::Null));
+      next = entry(item, "Next").cloned();
+    }
+    Some((titles, first_destination?))
+  }
+
+  #[test]
+  fn outline_points_to_printed_destinations() {
+    let mut document = printed();
+    let headings = [
+      heading(2, "Scope", "scope"),
+      heading(2, "Hidden", "missing"),
+      heading(1, "Introduction", "intro"),
+    ];
+    add_outline(&mut document, &headings).unwrap();
+    let (titles, destination) = outline(&document).unwrap();
+    assert_eq!(titles, ["Scope", "Introduction"]);
+    assert_eq!(written(&destination), "[3 0 R /XYZ 0 400 0]");
+  }
+
+  #[test]
+  fn outline_is_not_added_without_destinations() {
+    let mut document = printed();
+    add_outline(&mut document, &[heading(1, "Hidden", "missing")]).unwrap();
+    assert!(outline(&document).is_none());
+    assert!(!document.catalog().unwrap().has(b"PageMode"));
+  }
+
+  #[test]
+  fn headings_script_selects_requested_levels() {
+    let script = headings_script(&(2..=3));
+    assert!(script.contains(r#"querySelectorAll("h2,h3")"#));
+    let script = headings_script(&(0..=9));
+    assert!(script.contains(r#"querySelectorAll("h1,h2,h3,h4,h5,h6")"#));
+  }
+}
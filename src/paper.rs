@@ -30,15 +30,44 @@
 
 //! # Paper definitions
 
-//TODO Add paper sizes from https://papersizes.io
-
-use crate::errors::{err_invalid_paper_format, HtopError};
+use crate::errors::{err_invalid_length, err_invalid_paper_format, err_paper_size_out_of_range, HtopError, Result};
 
 /// Length of the inch expressed in millimeters.
 const MM_PER_INCH: f64 = 25.4;
 
+/// Number of points per inch.
+const PT_PER_INCH: f64 = 72.0;
+
+/// Number of CSS pixels per inch.
+pub(crate) const PX_PER_INCH: f64 = 96.0;
+
+/// Number of picas per inch.
+const PC_PER_INCH: f64 = 6.0;
+
+/// Minimal paper dimension in inches accepted by headless chrome (one CSS pixel).
+pub const MIN_PAPER_DIMENSION: f64 = 1.0 / PX_PER_INCH;
+
+/// Maximal paper dimension in inches (PDF page size limit of 14400 points).
+pub const MAX_PAPER_DIMENSION: f64 = 200.0;
+
+/// Length units accepted in paper and margin dimensions with their size in inches.
+const UNITS: [(&str, f64); 6] = [
+  ("mm", 1.0 / MM_PER_INCH),
+  ("cm", 10.0 / MM_PER_INCH),
+  ("in", 1.0),
+  ("pt", 1.0 / PT_PER_INCH),
+  ("px", 1.0 / PX_PER_INCH),
+  ("pc", 1.0 / PC_PER_INCH),
+];
+
 /// Paper size definitions.
+///
+/// ISO and JIS sizes are defined in millimeters,
+/// North American, ANSI and Architectural sizes are defined in inches,
+/// see <https://papersizes.io> for details.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PaperSize {
+  // ISO 216 A series
   A0,
   A1,
   A2,
@@ -46,26 +75,155 @@ pub enum PaperSize {
   A4,
   A5,
   A6,
+  A7,
+  A8,
+  A9,
+  A10,
+  // ISO 216 B series
+  B0,
+  B1,
+  B2,
+  B3,
+  B4,
+  B5,
+  B6,
+  B7,
+  B8,
+  B9,
+  B10,
+  // ISO 269 C series (envelopes)
+  C0,
+  C1,
+  C2,
+  C3,
+  C4,
+  C5,
+  C6,
+  C7,
+  C8,
+  C9,
+  C10,
+  // North American sizes
+  Letter,
+  Legal,
+  Tabloid,
+  Ledger,
+  Executive,
+  HalfLetter,
+  // ANSI sizes
+  AnsiA,
+  AnsiB,
+  AnsiC,
+  AnsiD,
+  AnsiE,
+  // Architectural sizes
+  ArchA,
+  ArchB,
+  ArchC,
+  ArchD,
+  ArchE,
+  // JIS B series
+  JisB0,
+  JisB1,
+  JisB2,
+  JisB3,
+  JisB4,
+  JisB5,
+  JisB6,
+  JisB7,
+  JisB8,
+  JisB9,
+  JisB10,
+}
+
+impl TryFrom<&str> for PaperSize {
+  type Error = HtopError;
+  /// Converts [PaperSize] from paper name.
+  ///
+  /// Matching is case-insensitive and ignores spaces, hyphens and underscores,
+  /// so `Letter`, `us-letter` and `US_LETTER` all denote the same paper size.
+  fn try_from(value: &str) -> Result<Self> {
+    let name = value
+      .chars()
+      .filter(|ch| !matches!(ch, ' ' | '-' | '_'))
+      .collect::<String>()
+      .to_lowercase();
+    match name.as_str() {
+      "a0" => Ok(Self::A0),
+      "a1" => Ok(Self::A1),
+      "a2" => Ok(Self::A2),
+      "a3" => Ok(Self::A3),
+      "a4" => Ok(Self::A4),
+      "a5" => Ok(Self::A5),
+      "a6" => Ok(Self::A6),
+      "a7" => Ok(Self::A7),
+      "a8" => Ok(Self::A8),
+      "a9" => Ok(Self::A9),
+      "a10" => Ok(Self::A10),
+      "b0" => Ok(Self::B0),
+      "b1" => Ok(Self::B1),
+      "b2" => Ok(Self::B2),
+      "b3" => Ok(Self::B3),
+      "b4" => Ok(Self::B4),
+      "b5" => Ok(Self::B5),
+      "b6" => Ok(Self::B6),
+      "b7" => Ok(Self::B7),
+      "b8" => Ok(Self::B8),
+      "b9" => Ok(Self::B9),
+      "b10" => Ok(Self::B10),
+      "c0" => Ok(Self::C0),
+      "c1" => Ok(Self::C1),
+      "c2" => Ok(Self::C2),
+      "c3" => Ok(Self::C3),
+      "c4" => Ok(Self::C4),
+      "c5" => Ok(Self::C5),
+      "c6" => Ok(Self::C6),
+      "c7" => Ok(Self::C7),
+      "c8" => Ok(Self::C8),
+      "c9" => Ok(Self::C9),
+      "c10" => Ok(Self::C10),
+      "letter" | "usletter" => Ok(Self::Letter),
+      "legal" | "uslegal" => Ok(Self::Legal),
+      "tabloid" | "ustabloid" => Ok(Self::Tabloid),
+      "ledger" | "usledger" => Ok(Self::Ledger),
+      "executive" | "usexecutive" => Ok(Self::Executive),
+      "halfletter" | "ushalfletter" | "statement" => Ok(Self::HalfLetter),
+      "ansia" => Ok(Self::AnsiA),
+      "ansib" => Ok(Self::AnsiB),
+      "ansic" => Ok(Self::AnsiC),
+      "ansid" => Ok(Self::AnsiD),
+      "ansie" => Ok(Self::AnsiE),
+      "archa" => Ok(Self::ArchA),
+      "archb" => Ok(Self::ArchB),
+      "archc" => Ok(Self::ArchC),
+      "archd" => Ok(Self::ArchD),
+      "arche" => Ok(Self::ArchE),
+      "jisb0" | "b0jis" => Ok(Self::JisB0),
+      "jisb1" | "b1jis" => Ok(Self::JisB1),
+      "jisb2" | "b2jis" => Ok(Self::JisB2),
+      "jisb3" | "b3jis" => Ok(Self::JisB3),
+      "jisb4" | "b4jis" => Ok(Self::JisB4),
+      "jisb5" | "b5jis" => Ok(Self::JisB5),
+      "jisb6" | "b6jis" => Ok(Self::JisB6),
+      "jisb7" | "b7jis" => Ok(Self::JisB7),
+      "jisb8" | "b8jis" => Ok(Self::JisB8),
+      "jisb9" | "b9jis" => Ok(Self::JisB9),
+      "jisb10" | "b10jis" => Ok(Self::JisB10),
+      _ => Err(err_invalid_paper_format(value)),
+    }
+  }
 }
 
 impl TryFrom<&String> for PaperSize {
   type Error = HtopError;
   /// Converts [PaperSize] from a reference to [String].
-  fn try_from(value: &String) -> Result<Self, Self::Error> {
-    match value.as_str() {
-      "A0" => Ok(Self::A0),
-      "A1" => Ok(Self::A1),
-      "A2" => Ok(Self::A2),
-      "A3" => Ok(Self::A3),
-      "A4" => Ok(Self::A4),
-      "A5" => Ok(Self::A5),
-      "A6" => Ok(Self::A6),
-      other => Err(err_invalid_paper_format(other)),
-    }
+  fn try_from(value: &String) -> Result<Self> {
+    Self::try_from(value.as_str())
   }
 }
 
 /// Paper properties.
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Paper {
   /// Paper width in inches.
   width: f64,
@@ -76,20 +234,87 @@ pub struct Paper {
 impl Paper {
   /// Creates a new paper with specified size.
   pub fn new(paper_size: PaperSize) -> Self {
-    // get paper size in millimeters
+    // get paper size in inches
     let (width, height) = match paper_size {
-      PaperSize::A0 => (841.0, 1189.0),
-      PaperSize::A1 => (594.0, 841.0),
-      PaperSize::A2 => (420.0, 594.0),
-      PaperSize::A3 => (297.0, 420.0),
-      PaperSize::A4 => (210.0, 297.0),
-      PaperSize::A5 => (148.0, 210.0),
-      PaperSize::A6 => (105.0, 148.0),
+      PaperSize::A0 => from_mm(841.0, 1189.0),
+      PaperSize::A1 => from_mm(594.0, 841.0),
+      PaperSize::A2 => from_mm(420.0, 594.0),
+      PaperSize::A3 => from_mm(297.0, 420.0),
+      PaperSize::A4 => from_mm(210.0, 297.0),
+      PaperSize::A5 => from_mm(148.0, 210.0),
+      PaperSize::A6 => from_mm(105.0, 148.0),
+      PaperSize::A7 => from_mm(74.0, 105.0),
+      PaperSize::A8 => from_mm(52.0, 74.0),
+      PaperSize::A9 => from_mm(37.0, 52.0),
+      PaperSize::A10 => from_mm(26.0, 37.0),
+      PaperSize::B0 => from_mm(1000.0, 1414.0),
+      PaperSize::B1 => from_mm(707.0, 1000.0),
+      PaperSize::B2 => from_mm(500.0, 707.0),
+      PaperSize::B3 => from_mm(353.0, 500.0),
+      PaperSize::B4 => from_mm(250.0, 353.0),
+      PaperSize::B5 => from_mm(176.0, 250.0),
+      PaperSize::B6 => from_mm(125.0, 176.0),
+      PaperSize::B7 => from_mm(88.0, 125.0),
+      PaperSize::B8 => from_mm(62.0, 88.0),
+      PaperSize::B9 => from_mm(44.0, 62.0),
+      PaperSize::B10 => from_mm(31.0, 44.0),
+      PaperSize::C0 => from_mm(917.0, 1297.0),
+      PaperSize::C1 => from_mm(648.0, 917.0),
+      PaperSize::C2 => from_mm(458.0, 648.0),
+      PaperSize::C3 => from_mm(324.0, 458.0),
+      PaperSize::C4 => from_mm(229.0, 324.0),
+      PaperSize::C5 => from_mm(162.0, 229.0),
+      PaperSize::C6 => from_mm(114.0, 162.0),
+      PaperSize::C7 => from_mm(81.0, 114.0),
+      PaperSize::C8 => from_mm(57.0, 81.0),
+      PaperSize::C9 => from_mm(40.0, 57.0),
+      PaperSize::C10 => from_mm(28.0, 40.0),
+      PaperSize::Letter => (8.5, 11.0),
+      PaperSize::Legal => (8.5, 14.0),
+      PaperSize::Tabloid => (11.0, 17.0),
+      PaperSize::Ledger => (17.0, 11.0),
+      PaperSize::Executive => (7.25, 10.5),
+      PaperSize::HalfLetter => (5.5, 8.5),
+      PaperSize::AnsiA => (8.5, 11.0),
+      PaperSize::AnsiB => (11.0, 17.0),
+      PaperSize::AnsiC => (17.0, 22.0),
+      PaperSize::AnsiD => (22.0, 34.0),
+      PaperSize::AnsiE => (34.0, 44.0),
+      PaperSize::ArchA => (9.0, 12.0),
+      PaperSize::ArchB => (12.0, 18.0),
+      PaperSize::ArchC => (18.0, 24.0),
+      PaperSize::ArchD => (24.0, 36.0),
+      PaperSize::ArchE => (36.0, 48.0),
+      PaperSize::JisB0 => from_mm(1030.0, 1456.0),
+      PaperSize::JisB1 => from_mm(728.0, 1030.0),
+      PaperSize::JisB2 => from_mm(515.0, 728.0),
+      PaperSize::JisB3 => from_mm(364.0, 515.0),
+      PaperSize::JisB4 => from_mm(257.0, 364.0),
+      PaperSize::JisB5 => from_mm(182.0, 257.0),
+      PaperSize::JisB6 => from_mm(128.0, 182.0),
+      PaperSize::JisB7 => from_mm(91.0, 128.0),
+      PaperSize::JisB8 => from_mm(64.0, 91.0),
+      PaperSize::JisB9 => from_mm(45.0, 64.0),
+      PaperSize::JisB10 => from_mm(32.0, 45.0),
     };
-    // create paper with sizes in inches
-    Self {
-      width: width / MM_PER_INCH,
-      height: height / MM_PER_INCH,
+    Self { width, height }
+  }
+
+  /// Creates a new paper with custom size given in inches.
+  ///
+  /// Both dimensions must be within the range accepted by headless chrome,
+  /// see [MIN_PAPER_DIMENSION] and [MAX_PAPER_DIMENSION].
+  pub fn custom(width: f64, height: f64) -> Result<Self> {
+    let in_range = |dimension: f64| (MIN_PAPER_DIMENSION..=MAX_PAPER_DIMENSION).contains(&dimension);
+    if in_range(width) && in_range(height) {
+      Ok(Self { width, height })
+    } else {
+      Err(err_paper_size_out_of_range(
+        width,
+        height,
+        MIN_PAPER_DIMENSION,
+        MAX_PAPER_DIMENSION,
+      ))
     }
   }
 
@@ -103,3 +328,213 @@ impl Paper {
     self.height
   }
 }
+
+impl TryFrom<&str> for Paper {
+  type Error = HtopError;
+  /// Converts [Paper] from paper name like `A4` or from custom dimensions like `80mmx200mm`.
+  ///
+  /// When only the second dimension has a unit, like in `8.5x11in`,
+  /// this unit applies to both dimensions.
+  fn try_from(value: &str) -> Result<Self> {
+    if let Ok(paper_size) = PaperSize::try_from(value) {
+      return Ok(Self::new(paper_size));
+    }
+    let dimensions = value.trim();
+    for (index, separator) in dimensions
+      .char_indices()
+      .filter(|(_, ch)| matches!(ch, 'x' | 'X' | '*'))
+    {
+      let first = dimensions[..index].trim();
+      let second = dimensions[index + separator.len_utf8()..].trim();
+      if let Ok(height) = parse_length(second) {
+        let width = if first.parse::<f64>().is_ok() {
+          let unit = second.trim_start_matches(|ch: char| ch.is_ascii_digit() || ch == '.');
+          parse_length(&format!("{first}{unit}"))
+        } else {
+          parse_length(first)
+        };
+        if let Ok(width) = width {
+          return Self::custom(width, height);
+        }
+      }
+    }
+    Err(err_invalid_paper_format(value))
+  }
+}
+
+impl TryFrom<&String> for Paper {
+  type Error = HtopError;
+  /// Converts [Paper] from a reference to [String].
+  fn try_from(value: &String) -> Result<Self> {
+    Self::try_from(value.as_str())
+  }
+}
+
+/// Parses the length with unit like `10mm`, `8.5in` or `12pt` and returns its value in inches.
+///
+/// Accepted units are `mm`, `cm`, `in`, `pt`, `px` and `pc`.
+pub fn parse_length(value: &str) -> Result<f64> {
+  let length = value.trim().to_lowercase();
+  for (unit, inches) in UNITS {
+    if let Some(number) = length.strip_suffix(unit) {
+      return match number.trim().parse::<f64>() {
+        Ok(number) if number.is_finite() && number >= 0.0 => Ok(number * inches),
+        _ => Err(err_invalid_length(value)),
+      };
+    }
+  }
+  Err(err_invalid_length(value))
+}
+
+/// Converts paper size given in millimeters into inches.
+fn from_mm(width: f64, height: f64) -> (f64, f64) {
+  (width / MM_PER_INCH, height / MM_PER_INCH)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Asserts that both dimensions are equal within rounding of millimeter conversions.
+  fn assert_size(paper: Paper, width: f64, height: f64) {
+    assert!(
+      (paper.width() - width).abs() < 1e-9,
+      "width {} != {}",
+      paper.width(),
+      width
+    );
+    assert!(
+      (paper.height() - height).abs() < 1e-9,
+      "height {} != {}",
+      paper.height(),
+      height
+    );
+  }
+
+  #[test]
+  fn paper_names_are_case_and_separator_insensitive() {
+    for name in ["Letter", "letter", "us-letter", "US_LETTER", "US Letter"] {
+      assert_eq!(PaperSize::try_from(name).unwrap(), PaperSize::Letter, "{}", name);
+    }
+    assert_eq!(PaperSize::try_from("a4").unwrap(), PaperSize::A4);
+    assert_eq!(PaperSize::try_from("JIS-B5").unwrap(), PaperSize::JisB5);
+    assert_eq!(PaperSize::try_from("b5-jis").unwrap(), PaperSize::JisB5);
+    assert_eq!(PaperSize::try_from("ANSI D").unwrap(), PaperSize::AnsiD);
+    assert_eq!(PaperSize::try_from("Arch-E").unwrap(), PaperSize::ArchE);
+    assert_eq!(PaperSize::try_from("statement").unwrap(), PaperSize::HalfLetter);
+  }
+
+  #[test]
+  fn unknown_paper_names_are_rejected() {
+    for name in ["A11", "B", "letterx", "", "D4"] {
+      assert!(
+        matches!(PaperSize::try_from(name), Err(HtopError::InvalidPaper(_))),
+        "{}",
+        name
+      );
+    }
+  }
+
+  #[test]
+  fn paper_catalog_sizes() {
+    assert_size(Paper::new(PaperSize::A4), 210.0 / MM_PER_INCH, 297.0 / MM_PER_INCH);
+    assert_size(Paper::new(PaperSize::A0), 841.0 / MM_PER_INCH, 1189.0 / MM_PER_INCH);
+    assert_size(Paper::new(PaperSize::B5), 176.0 / MM_PER_INCH, 250.0 / MM_PER_INCH);
+    assert_size(Paper::new(PaperSize::C4), 229.0 / MM_PER_INCH, 324.0 / MM_PER_INCH);
+    assert_size(Paper::new(PaperSize::JisB5), 182.0 / MM_PER_INCH, 257.0 / MM_PER_INCH);
+    assert_size(Paper::new(PaperSize::Letter), 8.5, 11.0);
+    assert_size(Paper::new(PaperSize::Legal), 8.5, 14.0);
+    assert_size(Paper::new(PaperSize::Ledger), 17.0, 11.0);
+    assert_size(Paper::new(PaperSize::AnsiC), 17.0, 22.0);
+    assert_size(Paper::new(PaperSize::ArchA), 9.0, 12.0);
+  }
+
+  #[test]
+  fn paper_is_parsed_from_name() {
+    assert_eq!(Paper::try_from("Legal").unwrap(), Paper::new(PaperSize::Legal));
+    assert_eq!(Paper::try_from(" a3 ").unwrap(), Paper::new(PaperSize::A3));
+  }
+
+  #[test]
+  fn custom_paper_sizes() {
+    assert_size(
+      Paper::try_from("80mmx200mm").unwrap(),
+      80.0 / MM_PER_INCH,
+      200.0 / MM_PER_INCH,
+    );
+    assert_size(Paper::try_from("8.5in x 11in").unwrap(), 8.5, 11.0);
+    assert_size(
+      Paper::try_from("21cm*29.7cm").unwrap(),
+      210.0 / MM_PER_INCH,
+      297.0 / MM_PER_INCH,
+    );
+    assert_size(Paper::try_from("612ptX792pt").unwrap(), 8.5, 11.0);
+    assert_size(Paper::try_from("4inx100mm").unwrap(), 4.0, 100.0 / MM_PER_INCH);
+  }
+
+  #[test]
+  fn unit_of_second_dimension_applies_to_unitless_first() {
+    assert_size(Paper::try_from("8.5x11in").unwrap(), 8.5, 11.0);
+    assert_size(
+      Paper::try_from("100x150mm").unwrap(),
+      100.0 / MM_PER_INCH,
+      150.0 / MM_PER_INCH,
+    );
+  }
+
+  #[test]
+  fn custom_paper_sizes_without_units_are_rejected() {
+    for value in [
+      "210x297",
+      "80mmx200",
+      "x",
+      "80mm",
+      "80mmx",
+      "x200mm",
+      "80mm-200mm",
+      "axbmm",
+    ] {
+      assert!(
+        matches!(Paper::try_from(value), Err(HtopError::InvalidPaper(_))),
+        "{}",
+        value
+      );
+    }
+  }
+
+  #[test]
+  fn custom_paper_sizes_out_of_range_are_rejected() {
+    assert!(matches!(Paper::try_from("0mmx100mm"), Err(HtopError::InvalidPaper(_))));
+    assert!(matches!(Paper::try_from("10inx201in"), Err(HtopError::InvalidPaper(_))));
+    assert!(Paper::custom(MIN_PAPER_DIMENSION, MAX_PAPER_DIMENSION).is_ok());
+  }
+
+  /// Asserts that the length is parsed into expected number of inches.
+  fn assert_length(value: &str, inches: f64) {
+    let length = parse_length(value).unwrap();
+    assert!((length - inches).abs() < 1e-9, "{} != {}", length, inches);
+  }
+
+  #[test]
+  fn lengths_are_converted_to_inches() {
+    assert_length("1in", 1.0);
+    assert_length("25.4mm", 1.0);
+    assert_length("2.54cm", 1.0);
+    assert_length("72pt", 1.0);
+    assert_length("96px", 1.0);
+    assert_length("6pc", 1.0);
+    assert_length(" 0.5 IN ", 0.5);
+    assert_length("0mm", 0.0);
+  }
+
+  #[test]
+  fn invalid_lengths_are_rejected() {
+    for value in ["", "10", "mm", "-1mm", "10 meters", "1e400mm", "NaNmm"] {
+      assert!(
+        matches!(parse_length(value), Err(HtopError::InvalidOption(_))),
+        "{}",
+        value
+      );
+    }
+  }
+}
@@ -0,0 +1,979 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # PDF documents
+//!
+//! Post-processing of `PDF` documents printed by headless chrome,
+//! like adding outlines or merging them into a single document.
+//!
+//! Documents are read and written with [lopdf], this module only rearranges their objects.
+//! Page contents are not interpreted.
+
+use crate::errors::{err_pdf, Result};
+use crate::utils::percent_decode;
+pub use lopdf::{Dictionary, Object, ObjectId, Stream, StringFormat};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// Attributes of page tree nodes inherited by pages.
+const INHERITED_ATTRIBUTES: [&str; 4] = ["Resources", "MediaBox", "CropBox", "Rotate"];
+
+/// Maximal number of references followed when resolving an object.
+const MAX_REFERENCE_DEPTH: usize = 32;
+
+/// Greatest number of an indirect object, the implementation limit given by the `PDF` specification.
+const MAX_OBJECT_ID: u32 = 8_388_607;
+
+/// Trailer entries kept when the document is written, other entries describe the cross-reference.
+const TRAILER_ENTRIES: [&str; 3] = ["Root", "Info", "ID"];
+
+/// Creates a name object.
+pub fn name(name: &str) -> Object {
+  Object::Name(name.as_bytes().to_vec())
+}
+
+/// Creates a text string object, encoded in UTF-16BE when it is not plain ASCII.
+pub fn text(text: &str) -> Object {
+  if text.is_ascii() {
+    Object::string_literal(text)
+  } else {
+    let mut bytes = vec![0xFE, 0xFF];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+    Object::String(bytes, StringFormat::Hexadecimal)
+  }
+}
+
+/// Returns the name or string bytes.
+pub fn as_bytes(object: &Object) -> Option<&[u8]> {
+  match object {
+    Object::Name(bytes) | Object::String(bytes, _) => Some(bytes),
+    _ => None,
+  }
+}
+
+/// Returns the text of a string object, decoded from UTF-16BE when it starts with byte order mark.
+pub fn as_text(object: &Object) -> Option<String> {
+  let Object::String(bytes, _) = object else {
+    return None;
+  };
+  Some(match bytes.strip_prefix(&[0xFE, 0xFF]) {
+    Some(utf16) => String::from_utf16_lossy(
+      &utf16
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect::<Vec<_>>(),
+    ),
+    None => bytes.iter().map(|byte| *byte as char).collect(),
+  })
+}
+
+/// Returns the dictionary, including the dictionary of a stream.
+pub fn as_dictionary(object: &Object) -> Option<&Dictionary> {
+  match object {
+    Object::Dictionary(dictionary) => Some(dictionary),
+    Object::Stream(stream) => Some(&stream.dict),
+    _ => None,
+  }
+}
+
+/// Returns the mutable dictionary, including the dictionary of a stream.
+pub fn as_dictionary_mut(object: &mut Object) -> Option<&mut Dictionary> {
+  match object {
+    Object::Dictionary(dictionary) => Some(dictionary),
+    Object::Stream(stream) => Some(&mut stream.dict),
+    _ => None,
+  }
+}
+
+/// Returns the entry of the dictionary, `None` when missing.
+pub fn entry<'a>(dictionary: &'a Dictionary, key: &str) -> Option<&'a Object> {
+  dictionary.get(key.as_bytes()).ok()
+}
+
+/// Returns `true` when the entry of the dictionary is a name equal to specified one.
+pub fn has_name(dictionary: &Dictionary, key: &str, name: &str) -> bool {
+  matches!(entry(dictionary, key), Some(Object::Name(bytes)) if bytes == name.as_bytes())
+}
+
+/// Calls the function for every reference contained in the object.
+fn for_each_reference(object: &Object, f: &mut impl FnMut(ObjectId)) {
+  match object {
+    Object::Reference(id) => f(*id),
+    Object::Array(items) => items.iter().for_each(|item| for_each_reference(item, f)),
+    Object::Dictionary(dictionary) => dictionary.iter().for_each(|(_, value)| for_each_reference(value, f)),
+    Object::Stream(stream) => stream.dict.iter().for_each(|(_, value)| for_each_reference(value, f)),
+    _ => {}
+  }
+}
+
+/// Adds the offset to the numbers of all references contained in the object.
+fn offset_references(object: &mut Object, offset: u32) {
+  match object {
+    Object::Reference((id, _)) => *id += offset,
+    Object::Array(items) => items.iter_mut().for_each(|item| offset_references(item, offset)),
+    Object::Dictionary(dictionary) => offset_dictionary_references(dictionary, offset),
+    Object::Stream(stream) => offset_dictionary_references(&mut stream.dict, offset),
+    _ => {}
+  }
+}
+
+/// Adds the offset to the numbers of all references contained in the dictionary.
+fn offset_dictionary_references(dictionary: &mut Dictionary, offset: u32) {
+  dictionary
+    .iter_mut()
+    .for_each(|(_, value)| offset_references(value, offset));
+}
+
+/// `PDF` document.
+#[derive(Debug, Clone)]
+pub struct Document(lopdf::Document);
+
+impl Default for Document {
+  /// Creates an empty document without catalog.
+  fn default() -> Self {
+    let mut document = lopdf::Document::new();
+    document.version = "1.7".to_string();
+    Self(document)
+  }
+}
+
+impl Document {
+  /// Reads the document from `PDF` bytes.
+  pub fn load(data: &[u8]) -> Result<Self> {
+    let mut document = lopdf::Document::load_mem(data).map_err(|e| err_pdf(e.to_string()))?;
+    document.trailer = TRAILER_ENTRIES
+      .iter()
+      .filter_map(|key| {
+        document
+          .trailer
+          .get(key.as_bytes())
+          .ok()
+          .map(|value| (*key, value.clone()))
+      })
+      .collect();
+    let max_id = document.objects.keys().next_back().map(|(id, _)| *id);
+    document.max_id = document.max_id.max(max_id.unwrap_or_default());
+    Ok(Self(document))
+  }
+
+  /// Writes the document into `PDF` bytes.
+  pub fn save(&mut self) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    self.0.save_to(&mut data).map_err(|e| err_pdf(e.to_string()))?;
+    Ok(data)
+  }
+
+  /// Returns the trailer dictionary.
+  pub fn trailer(&self) -> &Dictionary {
+    &self.0.trailer
+  }
+
+  /// Returns the indirect object with specified identifier.
+  pub fn get(&self, id: ObjectId) -> Option<&Object> {
+    self.0.objects.get(&id)
+  }
+
+  /// Returns the mutable indirect object with specified identifier.
+  pub fn get_mut(&mut self, id: ObjectId) -> Option<&mut Object> {
+    self.0.objects.get_mut(&id)
+  }
+
+  /// Adds a new indirect object and returns its identifier.
+  pub fn add(&mut self, object: Object) -> ObjectId {
+    self.0.add_object(object)
+  }
+
+  /// Replaces the indirect object with specified identifier.
+  fn set(&mut self, id: ObjectId, object: Object) {
+    self.0.objects.insert(id, object);
+  }
+
+  /// Follows references and returns referenced direct object, [Object::Null] when missing.
+  pub fn resolve<'a>(&'a self, mut object: &'a Object) -> &'a Object {
+    for _ in 0..MAX_REFERENCE_DEPTH {
+      match object {
+        Object::Reference(id) => object = self.get(*id).unwrap_or(&Object::Null),
+        _ => return object,
+      }
+    }
+    &Object::Null
+  }
+
+  /// Returns the identifier of the document catalog.
+  pub fn catalog_id(&self) -> Result<ObjectId> {
+    match entry(&self.0.trailer, "Root") {
+      Some(Object::Reference(id)) => Ok(*id),
+      _ => Err(err_pdf("missing document catalog")),
+    }
+  }
+
+  /// Returns the document catalog.
+  pub fn catalog(&self) -> Result<&Dictionary> {
+    self
+      .get(self.catalog_id()?)
+      .and_then(as_dictionary)
+      .ok_or_else(|| err_pdf("invalid document catalog"))
+  }
+
+  /// Returns the mutable document catalog.
+  pub fn catalog_mut(&mut self) -> Result<&mut Dictionary> {
+    let id = self.catalog_id()?;
+    self
+      .get_mut(id)
+      .and_then(as_dictionary_mut)
+      .ok_or_else(|| err_pdf("invalid document catalog"))
+  }
+
+  /// Returns the identifiers of page objects, in page order.
+  pub fn pages(&self) -> Result<Vec<ObjectId>> {
+    Ok(self.inherited_pages()?.into_iter().map(|(id, _)| id).collect())
+  }
+
+  /// Returns the number of pages.
+  pub fn page_count(&self) -> Result<usize> {
+    Ok(self.inherited_pages()?.len())
+  }
+
+  /// Returns the identifiers of page objects with attributes inherited from the page tree, in page order.
+  fn inherited_pages(&self) -> Result<Vec<(ObjectId, Dictionary)>> {
+    let root = entry(self.catalog()?, "Pages").ok_or_else(|| err_pdf("missing page tree"))?;
+    let mut pages = vec![];
+    let mut visited = HashSet::new();
+    let mut pending = vec![(root.clone(), Dictionary::new())];
+    while let Some((node, mut inherited)) = pending.pop() {
+      let Object::Reference(id) = node else {
+        continue;
+      };
+      let Some(dictionary) = self.get(id).and_then(as_dictionary) else {
+        continue;
+      };
+      if !visited.insert(id) {
+        continue;
+      }
+      for attribute in INHERITED_ATTRIBUTES {
+        if let Some(value) = entry(dictionary, attribute) {
+          inherited.set(attribute, value.clone());
+        }
+      }
+      match self.resolve(entry(dictionary, "Kids").unwrap_or(&Object::Null)) {
+        Object::Array(kids) if !has_name(dictionary, "Type", "Page") => {
+          // kids are pushed in reverse order to be popped in page order
+          pending.extend(kids.iter().rev().map(|kid| (kid.clone(), inherited.clone())));
+        }
+        _ => pages.push((id, inherited)),
+      }
+    }
+    Ok(pages)
+  }
+
+  /// Replaces the page tree with a single node containing specified pages.
+  fn set_pages(&mut self, pages: Vec<(ObjectId, Dictionary)>) -> Result<()> {
+    let pages_id = self.add(Object::Null);
+    let mut kids = Vec::with_capacity(pages.len());
+    for (id, inherited) in pages {
+      if let Some(page) = self.get_mut(id).and_then(as_dictionary_mut) {
+        for (key, value) in inherited.iter() {
+          if !page.has(key) {
+            page.set(key.clone(), value.clone());
+          }
+        }
+        page.set("Parent", Object::Reference(pages_id));
+        kids.push(Object::Reference(id));
+      }
+    }
+    let mut node = Dictionary::new();
+    node.set("Type", name("Pages"));
+    node.set("Count", Object::Integer(kids.len() as i64));
+    node.set("Kids", Object::Array(kids));
+    self.set(pages_id, Object::Dictionary(node));
+    self.catalog_mut()?.set("Pages", Object::Reference(pages_id));
+    Ok(())
+  }
+
+  /// Keeps only pages in specified range (zero-based) and removes objects no longer used.
+  ///
+  /// The logical structure of tagged documents is removed, as it would refer to removed pages.
+  pub fn retain_pages(&mut self, range: Range<usize>) -> Result<()> {
+    let pages = self.inherited_pages()?;
+    let pages = pages.into_iter().skip(range.start).take(range.len()).collect();
+    self.set_pages(pages)?;
+    let catalog = self.catalog_mut()?;
+    catalog.remove(b"StructTreeRoot");
+    catalog.remove(b"MarkInfo");
+    self.prune();
+    Ok(())
+  }
+
+  /// Returns the document information dictionary, created when missing.
+  pub fn info_mut(&mut self) -> &mut Dictionary {
+    let id = match entry(&self.0.trailer, "Info") {
+      Some(Object::Reference(id)) if matches!(self.get(*id), Some(Object::Dictionary(_))) => *id,
+      _ => {
+        let id = self.add(Object::Dictionary(Dictionary::new()));
+        self.0.trailer.set("Info", Object::Reference(id));
+        id
+      }
+    };
+    self.get_mut(id).and_then(as_dictionary_mut).unwrap()
+  }
+
+  /// Replaces the document outline with entries nested by their levels,
+  /// and makes viewers open the outline with the document.
+  pub fn set_outline(&mut self, entries: Vec<OutlineEntry>) -> Result<()> {
+    let mut children = vec![vec![]; entries.len()];
+    let mut top_level = vec![];
+    let mut ancestors: Vec<usize> = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+      while ancestors
+        .last()
+        .is_some_and(|ancestor| entries[*ancestor].level >= entry.level)
+      {
+        ancestors.pop();
+      }
+      match ancestors.last() {
+        Some(parent) => children[*parent].push(index),
+        None => top_level.push(index),
+      }
+      ancestors.push(index);
+    }
+    let ids = entries.iter().map(|_| self.add(Object::Null)).collect::<Vec<_>>();
+    // all items are open, so the count of an item is the number of all its descendants
+    let mut counts = vec![0i64; entries.len()];
+    for index in (0..entries.len()).rev() {
+      counts[index] = children[index].iter().map(|child| 1 + counts[*child]).sum();
+    }
+    let root = self.add(Object::Null);
+    let link_siblings = |document: &mut Self, siblings: &[usize], parent: ObjectId| {
+      for (position, index) in siblings.iter().enumerate() {
+        let mut item = Dictionary::new();
+        item.set("Title", text(&entries[*index].title));
+        item.set("Parent", Object::Reference(parent));
+        if position > 0 {
+          item.set("Prev", Object::Reference(ids[siblings[position - 1]]));
+        }
+        if let Some(next) = siblings.get(position + 1) {
+          item.set("Next", Object::Reference(ids[*next]));
+        }
+        if let (Some(first), Some(last)) = (children[*index].first(), children[*index].last()) {
+          item.set("First", Object::Reference(ids[*first]));
+          item.set("Last", Object::Reference(ids[*last]));
+          item.set("Count", Object::Integer(counts[*index]));
+        }
+        item.set("Dest", entries[*index].destination.clone());
+        document.set(ids[*index], Object::Dictionary(item));
+      }
+    };
+    link_siblings(self, &top_level, root);
+    for (index, siblings) in children.iter().enumerate() {
+      link_siblings(self, siblings, ids[index]);
+    }
+    let top_level = top_level.into_iter().map(|index| ids[index]).collect::<Vec<_>>();
+    self.set_outline_root(root, top_level)?;
+    self.prune();
+    Ok(())
+  }
+
+  /// Returns the identifiers of top-level outline items.
+  fn outline_items(&self) -> Vec<ObjectId> {
+    let mut items = vec![];
+    let root = self.catalog().ok().and_then(|catalog| entry(catalog, "Outlines"));
+    let mut next = root
+      .and_then(|root| as_dictionary(self.resolve(root)))
+      .and_then(|root| entry(root, "First"))
+      .cloned();
+    while let Some(Object::Reference(id)) = next {
+      if items.contains(&id) {
+        break;
+      }
+      items.push(id);
+      next = self
+        .get(id)
+        .and_then(as_dictionary)
+        .and_then(|item| entry(item, "Next"))
+        .cloned();
+    }
+    items
+  }
+
+  /// Creates the outline root with specified top-level items, linking items as siblings.
+  fn set_outline_root(&mut self, root: ObjectId, items: Vec<ObjectId>) -> Result<()> {
+    let mut count = 0;
+    for (position, id) in items.iter().enumerate() {
+      let Some(item) = self.get_mut(*id).and_then(as_dictionary_mut) else {
+        continue;
+      };
+      item.set("Parent", Object::Reference(root));
+      item.remove(b"Prev");
+      item.remove(b"Next");
+      if position > 0 {
+        item.set("Prev", Object::Reference(items[position - 1]));
+      }
+      if let Some(next) = items.get(position + 1) {
+        item.set("Next", Object::Reference(*next));
+      }
+      count += 1
+        + entry(item, "Count")
+          .and_then(|count| count.as_i64().ok())
+          .unwrap_or_default()
+          .max(0);
+    }
+    let catalog = self.catalog_mut()?;
+    if items.is_empty() {
+      catalog.remove(b"Outlines");
+      return Ok(());
+    }
+    catalog.set("Outlines", Object::Reference(root));
+    catalog.set("PageMode", name("UseOutlines"));
+    let mut outlines = Dictionary::new();
+    outlines.set("Type", name("Outlines"));
+    outlines.set("First", Object::Reference(items[0]));
+    outlines.set("Last", Object::Reference(items[items.len() - 1]));
+    outlines.set("Count", Object::Integer(count));
+    self.set(root, Object::Dictionary(outlines));
+    Ok(())
+  }
+
+  /// Returns named destinations defined in the catalog `Dests` dictionary
+  /// and in the `Dests` name tree, as explicit destination arrays.
+  pub fn named_destinations(&self) -> HashMap<Vec<u8>, Object> {
+    let mut destinations = HashMap::new();
+    let Ok(catalog) = self.catalog() else {
+      return destinations;
+    };
+    let mut add = |name: &[u8], value: &Object| {
+      let destination = match self.resolve(value) {
+        Object::Dictionary(dictionary) => self.resolve(entry(dictionary, "D").unwrap_or(&Object::Null)),
+        destination => destination,
+      };
+      if let Object::Array(_) = destination {
+        destinations.insert(name.to_vec(), destination.clone());
+      }
+    };
+    if let Some(Object::Dictionary(dests)) = entry(catalog, "Dests").map(|dests| self.resolve(dests)) {
+      dests.iter().for_each(|(name, value)| add(name, value));
+    }
+    let names = entry(catalog, "Names").map(|names| self.resolve(names));
+    let tree = names.and_then(as_dictionary).and_then(|names| entry(names, "Dests"));
+    let mut pending: Vec<&Object> = tree.into_iter().collect();
+    let mut visited = HashSet::new();
+    while let Some(node) = pending.pop() {
+      if let Object::Reference(id) = node {
+        if !visited.insert(*id) {
+          continue;
+        }
+      }
+      let Some(node) = as_dictionary(self.resolve(node)) else {
+        continue;
+      };
+      if let Some(Object::Array(kids)) = entry(node, "Kids").map(|kids| self.resolve(kids)) {
+        pending.extend(kids.iter());
+      }
+      if let Some(Object::Array(names)) = entry(node, "Names").map(|names| self.resolve(names)) {
+        for pair in names.chunks_exact(2) {
+          if let Some(name) = as_bytes(self.resolve(&pair[0])) {
+            add(name, &pair[1]);
+          }
+        }
+      }
+    }
+    destinations
+  }
+
+  /// Adds the offset to the numbers of all objects and to all references.
+  fn renumber(&mut self, offset: u32) -> Result<()> {
+    if self
+      .0
+      .max_id
+      .checked_add(offset)
+      .filter(|max_id| *max_id <= MAX_OBJECT_ID)
+      .is_none()
+    {
+      return Err(err_pdf("too many objects in merged document"));
+    }
+    let objects = std::mem::take(&mut self.0.objects);
+    self.0.objects = objects
+      .into_iter()
+      .map(|((id, generation), mut object)| {
+        offset_references(&mut object, offset);
+        ((id + offset, generation), object)
+      })
+      .collect();
+    offset_dictionary_references(&mut self.0.trailer, offset);
+    self.0.max_id += offset;
+    Ok(())
+  }
+
+  /// Replaces named destinations of link annotations on the page with explicit destinations,
+  /// and URI actions with internal links when `target` resolves the URI.
+  fn resolve_links(
+    &mut self,
+    page: ObjectId,
+    named: &dyn Fn(&[u8]) -> Option<Object>,
+    target: &dyn Fn(&[u8]) -> Option<Object>,
+  ) {
+    let Some(annotations) = self
+      .get(page)
+      .and_then(as_dictionary)
+      .and_then(|page| entry(page, "Annots"))
+      .map(|annotations| self.resolve(annotations).clone())
+    else {
+      return;
+    };
+    let Object::Array(mut annotations) = annotations else {
+      return;
+    };
+    for annotation in annotations.iter_mut() {
+      let resolved = match annotation {
+        Object::Reference(id) => self.get(*id).and_then(as_dictionary).cloned(),
+        Object::Dictionary(dictionary) => Some(dictionary.clone()),
+        _ => None,
+      };
+      let Some(mut dictionary) = resolved.filter(|dictionary| has_name(dictionary, "Subtype", "Link")) else {
+        continue;
+      };
+      let action = entry(&dictionary, "A")
+        .and_then(|action| as_dictionary(self.resolve(action)))
+        .cloned();
+      let destination = match (entry(&dictionary, "Dest"), action) {
+        (Some(destination), _) => as_bytes(destination).and_then(named),
+        (None, Some(action)) if has_name(&action, "S", "GoTo") => {
+          entry(&action, "D").and_then(as_bytes).and_then(named)
+        }
+        (None, Some(action)) if has_name(&action, "S", "URI") => entry(&action, "URI")
+          .and_then(|uri| as_bytes(self.resolve(uri)))
+          .and_then(target),
+        _ => None,
+      };
+      let Some(destination) = destination else {
+        continue;
+      };
+      dictionary.remove(b"A");
+      dictionary.set("Dest", destination);
+      match annotation {
+        Object::Reference(id) => {
+          let id = *id;
+          self.set(id, Object::Dictionary(dictionary));
+        }
+        _ => *annotation = Object::Dictionary(dictionary),
+      }
+    }
+    if let Some(page) = self.get_mut(page).and_then(as_dictionary_mut) {
+      page.set("Annots", Object::Array(annotations));
+    }
+  }
+
+  /// Removes objects not reachable from the trailer.
+  pub fn prune(&mut self) {
+    let mut reachable = HashSet::new();
+    let mut pending = vec![];
+    for (_, value) in self.0.trailer.iter() {
+      for_each_reference(value, &mut |id| pending.push(id));
+    }
+    while let Some(id) = pending.pop() {
+      if reachable.insert(id) {
+        if let Some(object) = self.get(id) {
+          for_each_reference(object, &mut |id| pending.push(id));
+        }
+      }
+    }
+    self.0.objects.retain(|id, _| reachable.contains(id));
+  }
+}
+
+/// Entry of the document outline.
+#[derive(Debug, Clone)]
+pub struct OutlineEntry {
+  /// Nesting level, entries are nested in the nearest preceding entry with lower level.
+  pub level: u8,
+  /// Title displayed in the outline.
+  pub title: String,
+  /// Explicit destination of the entry.
+  pub destination: Object,
+}
+
+/// Printed document merged with other documents.
+pub struct Chapter {
+  /// URL of the printed page, links to this URL from other chapters point to this chapter.
+  pub url: String,
+  /// Printed document.
+  pub document: Document,
+}
+
+/// Merges printed documents into a single document, pages follow in the order of chapters.
+///
+/// Links to the URL of another chapter become internal links to the first page of that chapter,
+/// or to the named destination given by the URL fragment. Named destinations are replaced
+/// with explicit ones, and the logical structure of tagged documents is not preserved.
+/// Outlines of all chapters are joined into a single outline, metadata is taken from the first chapter.
+pub fn merge(chapters: Vec<Chapter>) -> Result<Document> {
+  let mut merged = Document::default();
+  let mut urls = vec![];
+  let mut chapter_pages = vec![];
+  let mut chapter_destinations = vec![];
+  let mut all_pages = vec![];
+  let mut outline_items = vec![];
+  let mut metadata = None;
+  for chapter in chapters {
+    let mut document = chapter.document;
+    document.renumber(merged.0.max_id)?;
+    let pages = document.inherited_pages()?;
+    chapter_pages.push(pages.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+    chapter_destinations.push(document.named_destinations());
+    outline_items.extend(document.outline_items());
+    if !merged.0.trailer.has(b"Info") {
+      if let Some(info) = entry(document.trailer(), "Info") {
+        merged.0.trailer.set("Info", info.clone());
+      }
+      metadata = entry(document.catalog()?, "Metadata").cloned();
+    }
+    urls.push(normalized_url(&chapter.url));
+    merged.0.objects.append(&mut document.0.objects);
+    merged.0.max_id = document.0.max_id;
+    all_pages.extend(pages);
+  }
+  let target = |url: &[u8]| -> Option<Object> {
+    let url = String::from_utf8_lossy(url);
+    let (address, fragment) = url.split_once('#').unwrap_or((&url, ""));
+    let index = urls
+      .iter()
+      .position(|chapter_url| *chapter_url == normalized_url(address))?;
+    let fragment_destination = [fragment.as_bytes().to_vec(), percent_decode(fragment).into_bytes()]
+      .iter()
+      .find_map(|name| chapter_destinations[index].get(name).cloned());
+    fragment_destination.or_else(|| {
+      let first_page = chapter_pages[index].first()?;
+      Some(Object::Array(vec![Object::Reference(*first_page), name("Fit")]))
+    })
+  };
+  for (index, pages) in chapter_pages.iter().enumerate() {
+    for page in pages {
+      merged.resolve_links(*page, &|name| chapter_destinations[index].get(name).cloned(), &target);
+    }
+  }
+  let mut catalog = Dictionary::new();
+  catalog.set("Type", name("Catalog"));
+  if let Some(metadata) = metadata {
+    catalog.set("Metadata", metadata);
+  }
+  let catalog_id = merged.add(Object::Dictionary(catalog));
+  merged.0.trailer.set("Root", Object::Reference(catalog_id));
+  merged.set_pages(all_pages)?;
+  let outline_root = merged.add(Object::Null);
+  merged.set_outline_root(outline_root, outline_items)?;
+  merged.prune();
+  Ok(merged)
+}
+
+/// Returns the URL without fragment and percent-encoding, used to compare chapter URLs.
+fn normalized_url(url: &str) -> String {
+  percent_decode(url.split('#').next().unwrap_or_default())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  /// Objects of a single page document, numbered from 1.
+  const SINGLE_PAGE: [&str; 4] = [
+    "<< /Type /Catalog /Pages 2 0 R >>",
+    "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 612 792] >>",
+    "<< /Type /Page /Parent 2 0 R /Contents 4 0 R >>",
+    "<< /Length 3 >>\nstream\nq Q\nendstream",
+  ];
+
+  /// Builds `PDF` bytes with objects numbered from 1 and a cross-reference table.
+  pub(crate) fn pdf(objects: &[&str], trailer: &str) -> Vec<u8> {
+    let mut data = b"%PDF-1.7\n".to_vec();
+    let mut offsets = vec![];
+    for (index, object) in objects.iter().enumerate() {
+      offsets.push(data.len());
+      data.extend(format!("{} 0 obj\n{}\nendobj\n", index + 1, object).bytes());
+    }
+    let xref = data.len();
+    data.extend(format!("xref\n0 {}\n0000000000 65535 f\r\n", objects.len() + 1).bytes());
+    for offset in offsets {
+      data.extend(format!("{:010} 00000 n\r\n", offset).bytes());
+    }
+    data.extend(format!("trailer\n{}\nstartxref\n{}\n%%EOF\n", trailer, xref).bytes());
+    data
+  }
+
+  /// Builds the single page document with a cross-reference table.
+  fn single_page() -> Vec<u8> {
+    pdf(&SINGLE_PAGE, "<< /Size 5 /Root 1 0 R >>")
+  }
+
+  /// Returns the object in `PDF` like syntax, used to compare objects.
+  pub(crate) fn written(object: &Object) -> String {
+    format!("{:?}", object)
+  }
+
+  /// Returns all objects in `PDF` like syntax.
+  fn all_objects(document: &Document) -> Vec<(ObjectId, String)> {
+    let objects = document.0.objects.iter();
+    objects.map(|(id, object)| (*id, written(object))).collect()
+  }
+
+  #[test]
+  fn document_is_loaded() {
+    let document = Document::load(&single_page()).unwrap();
+    assert_eq!(document.pages().unwrap(), vec![(3, 0)]);
+    assert_eq!(document.catalog_id().unwrap(), (1, 0));
+    let content = document.get((4, 0)).unwrap();
+    assert!(matches!(content, Object::Stream(stream) if stream.content == b"q Q"));
+  }
+
+  #[test]
+  fn objects_are_preserved_by_saving() {
+    let objects = [
+      SINGLE_PAGE[0],
+      SINGLE_PAGE[1],
+      "<< /Type /Page /Parent 2 0 R /Annots [5 0 R] >>",
+      SINGLE_PAGE[3],
+      "<< /Name /A#20B#2F /Text (a\\(b\\)\\\\c) /Hex <FEFF00e9> /Negative -1.5 \
+      /Flags [true false null] /Nested << /Empty [] >> >>",
+    ];
+    let mut document = Document::load(&pdf(&objects, "<< /Size 6 /Root 1 0 R >>")).unwrap();
+    let annotation = document.get((5, 0)).and_then(as_dictionary).unwrap();
+    assert_eq!(entry(annotation, "Name").and_then(as_bytes), Some(&b"A B/"[..]));
+    assert_eq!(entry(annotation, "Text").and_then(as_bytes), Some(&b"a(b)\\c"[..]));
+    assert_eq!(entry(annotation, "Hex").and_then(as_text).as_deref(), Some("\u{e9}"));
+    // entries describing the cross-reference are not kept
+    assert!(!document.trailer().has(b"Size"));
+    let saved = Document::load(&document.save().unwrap()).unwrap();
+    assert_eq!(all_objects(&saved), all_objects(&document));
+    assert_eq!(saved.catalog_id().unwrap(), document.catalog_id().unwrap());
+  }
+
+  #[test]
+  fn text_is_encoded_in_utf16_when_not_ascii() {
+    assert_eq!(as_bytes(&text("plain")), Some(&b"plain"[..]));
+    assert_eq!(as_bytes(&text("\u{e9}")), Some(&[0xFE, 0xFF, 0x00, 0xE9][..]));
+    for value in ["plain", "Zusammenfassung \u{fc}ber", ""] {
+      assert_eq!(as_text(&text(value)).as_deref(), Some(value));
+    }
+  }
+
+  #[test]
+  fn invalid_documents_are_rejected() {
+    assert!(Document::load(b"").is_err());
+    assert!(Document::load(b"<html></html>").is_err());
+    let data = single_page();
+    assert!(Document::load(&data[..data.len() / 2]).is_err());
+  }
+
+  /// Returns the dictionary of the indirect object.
+  fn dictionary(document: &Document, id: ObjectId) -> &Dictionary {
+    document.get(id).and_then(as_dictionary).unwrap()
+  }
+
+  /// Returns the annotations of the page, resolving references.
+  fn annotations(document: &Document, page: ObjectId) -> Vec<Dictionary> {
+    let annotations = document.resolve(entry(dictionary(document, page), "Annots").unwrap());
+    annotations
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|annotation| as_dictionary(document.resolve(annotation)).unwrap().clone())
+      .collect()
+  }
+
+  /// Title and descendant count of an outline item, with titles and counts of its children.
+  type OutlineItem = (String, i64, Vec<(String, i64)>);
+
+  /// Returns outline items following `First` and `Next` links.
+  fn outline(document: &Document, parent: &Dictionary) -> Vec<OutlineItem> {
+    let mut items = vec![];
+    let mut next = entry(parent, "First").cloned();
+    while let Some(Object::Reference(id)) = next {
+      let item = dictionary(document, id);
+      let children = outline(document, item)
+        .into_iter()
+        .map(|(title, count, _)| (title, count))
+        .collect();
+      items.push((
+        entry(item, "Title").and_then(as_text).unwrap(),
+        entry(item, "Count")
+          .and_then(|count| count.as_i64().ok())
+          .unwrap_or_default(),
+        children,
+      ));
+      next = entry(item, "Next").cloned();
+    }
+    items
+  }
+
+  /// Creates an outline entry pointing to the page.
+  fn outline_entry(level: u8, title: &str, page: u32) -> OutlineEntry {
+    OutlineEntry {
+      level,
+      title: title.to_string(),
+      destination: Object::Array(vec![Object::Reference((page, 0)), name("Fit")]),
+    }
+  }
+
+  #[test]
+  fn outline_entries_are_nested_by_levels() {
+    let mut document = Document::load(&single_page()).unwrap();
+    document
+      .set_outline(vec![
+        outline_entry(1, "Introduction", 3),
+        outline_entry(2, "Scope", 3),
+        outline_entry(3, "Details", 3),
+        outline_entry(2, "Terms", 3),
+        outline_entry(1, "Zusammenfassung \u{fc}ber", 3),
+        outline_entry(3, "Skipped level", 3),
+      ])
+      .unwrap();
+    let catalog = document.catalog().unwrap();
+    assert!(has_name(catalog, "PageMode", "UseOutlines"));
+    let root = as_dictionary(document.resolve(entry(catalog, "Outlines").unwrap())).unwrap();
+    assert_eq!(entry(root, "Count").map(written).as_deref(), Some("6"));
+    assert_eq!(
+      outline(&document, root),
+      vec![
+        (
+          "Introduction".to_string(),
+          3,
+          vec![("Scope".to_string(), 1), ("Terms".to_string(), 0)]
+        ),
+        (
+          "Zusammenfassung \u{fc}ber".to_string(),
+          1,
+          vec![("Skipped level".to_string(), 0)]
+        ),
+      ]
+    );
+    let saved = Document::load(&document.save().unwrap()).unwrap();
+    assert_eq!(all_objects(&saved), all_objects(&document));
+  }
+
+  #[test]
+  fn pages_out_of_range_are_removed() {
+    let objects = [
+      "<< /Type /Catalog /Pages 2 0 R /StructTreeRoot 7 0 R >>",
+      "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 /MediaBox [0 0 612 792] >>",
+      "<< /Type /Page /Parent 2 0 R /Contents 6 0 R >>",
+      "<< /Type /Page /Parent 2 0 R /Rotate 90 >>",
+      "<< /Type /Page /Parent 2 0 R >>",
+      "<< /Length 3 >>\nstream\nq Q\nendstream",
+      "<< /Type /StructTreeRoot >>",
+    ];
+    let mut document = Document::load(&pdf(&objects, "<< /Size 8 /Root 1 0 R >>")).unwrap();
+    document.retain_pages(1..2).unwrap();
+    assert_eq!(document.pages().unwrap(), vec![(4, 0)]);
+    let page = dictionary(&document, (4, 0));
+    assert_eq!(entry(page, "Rotate").map(written).as_deref(), Some("90"));
+    assert!(page.has(b"MediaBox"));
+    assert!(!document.catalog().unwrap().has(b"StructTreeRoot"));
+    for removed in [3, 5, 6, 7] {
+      assert!(document.get((removed, 0)).is_none(), "{}", removed);
+    }
+  }
+
+  #[test]
+  fn chapters_are_merged_with_links_and_outlines() {
+    let first = [
+      "<< /Type /Catalog /Pages 2 0 R /Outlines 6 0 R >>",
+      "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 612 792] >>",
+      "<< /Type /Page /Parent 2 0 R /Annots [4 0 R 5 0 R] >>",
+      "<< /Type /Annot /Subtype /Link /A << /S /URI /URI (file:///book/second%20part.html#intro) >> >>",
+      "<< /Type /Annot /Subtype /Link /A << /S /URI /URI (https://example.com/) >> >>",
+      "<< /Type /Outlines /First 7 0 R /Last 7 0 R /Count 1 >>",
+      "<< /Title (First) /Parent 6 0 R /Dest [3 0 R /Fit] >>",
+      "<< /Title (Book) >>",
+    ];
+    let second = [
+      "<< /Type /Catalog /Pages 2 0 R /Names << /Dests 4 0 R >> >>",
+      "<< /Type /Pages /Kids [3 0 R 5 0 R] /Count 2 /MediaBox [0 0 595 842] >>",
+      "<< /Type /Page /Parent 2 0 R /Annots [<< /Subtype /Link /Dest (intro) >>] >>",
+      "<< /Names [(intro) [5 0 R /XYZ 0 842 0]] >>",
+      "<< /Type /Page /Parent 2 0 R /Annots [<< /Subtype /Link /A << /S /URI /URI (file:///book/first.html) >> >>] >>",
+      "<< /Title (Other) >>",
+    ];
+    let first = Document::load(&pdf(&first, "<< /Size 9 /Root 1 0 R /Info 8 0 R >>")).unwrap();
+    let mut second = Document::load(&pdf(&second, "<< /Size 7 /Root 1 0 R /Info 6 0 R >>")).unwrap();
+    second
+      .set_outline(vec![outline_entry(1, "Second", 3), outline_entry(2, "Introduction", 5)])
+      .unwrap();
+    let mut merged = merge(vec![
+      Chapter {
+        url: "file:///book/first.html".to_string(),
+        document: first,
+      },
+      Chapter {
+        url: "file:///book/second part.html".to_string(),
+        document: second,
+      },
+    ])
+    .unwrap();
+    let merged = Document::load(&merged.save().unwrap()).unwrap();
+    let pages = merged.pages().unwrap();
+    assert_eq!(pages.len(), 3);
+    let media_box = |page: ObjectId| entry(dictionary(&merged, page), "MediaBox").map(written);
+    assert_eq!(media_box(pages[0]).as_deref(), Some("[0 0 612 792]"));
+    assert_eq!(media_box(pages[2]).as_deref(), Some("[0 0 595 842]"));
+    let destination = |link: &Dictionary| entry(link, "Dest").map(written);
+    let explicit = |page: ObjectId, rest: &str| Some(format!("[{} {} R {}]", page.0, page.1, rest));
+    // link to another chapter with a fragment points to the named destination in that chapter
+    let links = annotations(&merged, pages[0]);
+    assert_eq!(destination(&links[0]), explicit(pages[2], "/XYZ 0 842 0"));
+    assert!(!links[0].has(b"A"));
+    // link outside of merged chapters is kept
+    assert!(has_name(
+      entry(&links[1], "A").and_then(as_dictionary).unwrap(),
+      "S",
+      "URI"
+    ));
+    // named destination is replaced with explicit one
+    let links = annotations(&merged, pages[1]);
+    assert_eq!(destination(&links[0]), explicit(pages[2], "/XYZ 0 842 0"));
+    // link to another chapter without a fragment points to its first page
+    let links = annotations(&merged, pages[2]);
+    assert_eq!(destination(&links[0]), explicit(pages[0], "/Fit"));
+    let info = as_dictionary(merged.resolve(entry(merged.trailer(), "Info").unwrap())).unwrap();
+    assert_eq!(entry(info, "Title").and_then(as_text).as_deref(), Some("Book"));
+    let catalog = merged.catalog().unwrap();
+    let root = as_dictionary(merged.resolve(entry(catalog, "Outlines").unwrap())).unwrap();
+    assert_eq!(entry(root, "Count").map(written).as_deref(), Some("3"));
+    assert_eq!(
+      outline(&merged, root),
+      vec![
+        ("First".to_string(), 0, vec![]),
+        ("Second".to_string(), 1, vec![("Introduction".to_string(), 0)]),
+      ]
+    );
+  }
+
+  #[test]
+  fn merging_without_chapters_gives_empty_document() {
+    assert!(merge(vec![]).is_ok_and(|document| document.page_count().unwrap() == 0));
+  }
+}
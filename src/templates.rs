@@ -0,0 +1,156 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Header and footer templates
+//!
+//! Templates are `HTML` fragments printed by headless chrome in page headers and footers.
+//! Elements with the following classes are filled in by headless chrome:
+//! - `date` - formatted print date,
+//! - `title` - document title,
+//! - `url` - document location,
+//! - `pageNumber` - current page number,
+//! - `totalPages` - total number of pages in the document.
+//!
+//! Additionally, `{{name}}` placeholders are replaced with values of user-defined variables,
+//! values are inserted as text, so characters with special meaning in `HTML` are escaped.
+
+use crate::errors::{err_invalid_template_variable, err_read_file, err_undefined_template_variable, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Type alias for template variables, mapping variable names to their values.
+pub type TemplateVariables = HashMap<String, String>;
+
+/// Template used when only one of header or footer templates is specified,
+/// prevents headless chrome from printing its default header or footer.
+pub const EMPTY_TEMPLATE: &str = "<span></span>";
+
+/// Loads the template from `HTML` file when specified value is a path to existing file,
+/// otherwise the value is treated as inline `HTML`.
+pub fn load_template(value: &str) -> Result<String> {
+  let path = Path::new(value);
+  if path.is_file() {
+    fs::read_to_string(path).map_err(|e| err_read_file(path, e))
+  } else {
+    Ok(value.to_string())
+  }
+}
+
+/// Parses template variable definition in the form `name=value`.
+pub fn parse_template_variable(definition: &str) -> Result<(String, String)> {
+  match definition.split_once('=') {
+    Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_string(), value.to_string())),
+    _ => Err(err_invalid_template_variable(definition)),
+  }
+}
+
+/// Replaces all `{{name}}` placeholders in the template with `HTML`-escaped values of variables.
+pub fn render_template(template: &str, variables: &TemplateVariables) -> Result<String> {
+  let mut rendered = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    rendered.push_str(&rest[..start]);
+    let after_start = &rest[start + 2..];
+    let Some(end) = after_start.find("}}") else {
+      rendered.push_str(&rest[start..]);
+      return Ok(rendered);
+    };
+    let name = after_start[..end].trim();
+    let value = variables
+      .get(name)
+      .ok_or_else(|| err_undefined_template_variable(name))?;
+    rendered.push_str(&escape_html(value));
+    rest = &after_start[end + 2..];
+  }
+  rendered.push_str(rest);
+  Ok(rendered)
+}
+
+/// Escapes characters with special meaning in `HTML` text and attribute values.
+fn escape_html(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Creates template variables from name and value pairs.
+  fn variables(pairs: &[(&str, &str)]) -> TemplateVariables {
+    pairs
+      .iter()
+      .map(|(name, value)| (name.to_string(), value.to_string()))
+      .collect()
+  }
+
+  #[test]
+  fn placeholders_are_replaced_with_values() {
+    let variables = variables(&[("client", "ACME"), ("year", "2023")]);
+    let rendered = render_template("<span>{{client}} &copy; {{ year }}</span>", &variables).unwrap();
+    assert_eq!(rendered, "<span>ACME &copy; 2023</span>");
+  }
+
+  #[test]
+  fn values_are_html_escaped() {
+    let variables = variables(&[("client", "<b>Tom & \"Jerry's\"</b>")]);
+    let rendered = render_template("<span title=\"{{client}}\">{{client}}</span>", &variables).unwrap();
+    assert_eq!(
+      rendered,
+      "<span title=\"&lt;b&gt;Tom &amp; &quot;Jerry&#39;s&quot;&lt;/b&gt;\">&lt;b&gt;Tom &amp; &quot;Jerry&#39;s&quot;&lt;/b&gt;</span>"
+    );
+  }
+
+  #[test]
+  fn unterminated_placeholder_is_kept() {
+    let rendered = render_template("<span>{{client</span>", &variables(&[])).unwrap();
+    assert_eq!(rendered, "<span>{{client</span>");
+  }
+
+  #[test]
+  fn undefined_variable_is_an_error() {
+    assert!(render_template("{{client}}", &variables(&[])).is_err());
+  }
+
+  #[test]
+  fn variable_definitions_are_parsed() {
+    assert_eq!(
+      parse_template_variable(" client =A=B").unwrap(),
+      ("client".to_string(), "A=B".to_string())
+    );
+    assert!(parse_template_variable("client").is_err());
+    assert!(parse_template_variable(" =value").is_err());
+  }
+}
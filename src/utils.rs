@@ -30,10 +30,39 @@
 
 //! # Utility functions
 
-use crate::defs::{HTML_EXTENSION, PDF_EXTENSION};
+use crate::defs::PDF_EXTENSION;
 use crate::errors::{err_canonicalize, err_file_name, Result};
+use std::cmp::Ordering as CmpOrdering;
 use std::env;
+use std::fmt;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Flag indicating if verbose messages are printed to standard error instead of standard output.
+static MESSAGES_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Redirects verbose messages to standard error, used when `PDF` is written to standard output.
+pub fn messages_to_stderr() {
+  MESSAGES_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Prints verbose message to standard output, or to standard error when redirected.
+pub fn print_message(args: fmt::Arguments) {
+  if MESSAGES_TO_STDERR.load(Ordering::Relaxed) {
+    eprintln!("{}", args);
+  } else {
+    println!("{}", args);
+  }
+}
+
+/// Prints verbose message like `println!`, see [print_message].
+macro_rules! message {
+  ($($arg:tt)*) => {
+    $crate::utils::print_message(format_args!($($arg)*))
+  };
+}
+
+pub(crate) use message;
 
 /// Converts file path into file URL string.
 pub fn file_url(file_path: &Path) -> Result<String> {
@@ -41,11 +70,150 @@ pub fn file_url(file_path: &Path) -> Result<String> {
     "file://{}",
     file_path
       .canonicalize()
-      .map_err(|e| err_canonicalize(file_path, e.to_string()))?
+      .map_err(|e| err_canonicalize(file_path, e))?
       .to_string_lossy()
   ))
 }
 
+/// Converts directory path into file URL string ending with a slash,
+/// so relative URLs are resolved against the directory itself.
+pub fn dir_url(dir_path: &Path) -> Result<String> {
+  let url = file_url(dir_path)?;
+  Ok(if url.ends_with('/') { url } else { format!("{}/", url) })
+}
+
+/// Inserts `<base>` element with specified URL into `HTML` document,
+/// right after the opening `<head>` tag, or at the beginning when there is no such tag.
+pub fn insert_base_url(html: &[u8], base_url: &str) -> Vec<u8> {
+  let base = format!(
+    "<base href=\"{}\">",
+    base_url.replace('&', "&amp;").replace('"', "&quot;")
+  );
+  let lowercase = html.to_ascii_lowercase();
+  let head_end = lowercase
+    .windows(5)
+    .enumerate()
+    .find(|(index, window)| {
+      *window == b"<head" && matches!(lowercase.get(index + 5), Some(b'>' | b' ' | b'\t' | b'\r' | b'\n'))
+    })
+    .and_then(|(index, _)| {
+      lowercase[index..]
+        .iter()
+        .position(|byte| *byte == b'>')
+        .map(|end| index + end + 1)
+    })
+    .unwrap_or(0);
+  [&html[..head_end], base.as_bytes(), &html[head_end..]].concat()
+}
+
+/// Returns `true` when the input is an `http`, `https`, `file` or `data` URL,
+/// printed directly instead of being resolved as a file path.
+pub fn is_url(input: &str) -> bool {
+  ["http://", "https://", "file://", "data:"].iter().any(|scheme| {
+    input
+      .get(..scheme.len())
+      .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+  })
+}
+
+/// Derives the output `PDF` file name from the last segment of the URL path,
+/// like `report.pdf` for `http://localhost:3000/report?id=1`.
+///
+/// When the path is empty, the host name is used, and `document.pdf` for `data` URLs.
+pub fn url_file_name(url: &str) -> String {
+  let Some((scheme, rest)) = url.split_once(':') else {
+    return format!("document.{}", PDF_EXTENSION);
+  };
+  if scheme.eq_ignore_ascii_case("data") {
+    return format!("document.{}", PDF_EXTENSION);
+  }
+  let rest = rest.trim_start_matches('/');
+  let rest = rest.split(['?', '#']).next().unwrap_or_default();
+  let (host, path) = if scheme.eq_ignore_ascii_case("file") {
+    ("", rest)
+  } else {
+    rest.split_once('/').unwrap_or((rest, ""))
+  };
+  let segment = percent_decode(path.rsplit('/').find(|segment| !segment.is_empty()).unwrap_or_default());
+  let stem = match segment.rsplit_once('.') {
+    Some((stem, _)) if !stem.is_empty() => stem.to_string(),
+    _ => segment,
+  };
+  let stem = if stem.is_empty() {
+    host.split(':').next().unwrap_or_default().to_string()
+  } else {
+    stem
+  };
+  let stem = stem
+    .chars()
+    .map(|ch| match ch {
+      '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+      ch if ch.is_control() => '_',
+      ch => ch,
+    })
+    .collect::<String>();
+  if stem.is_empty() || stem == "." || stem == ".." {
+    format!("document.{}", PDF_EXTENSION)
+  } else {
+    format!("{}.{}", stem, PDF_EXTENSION)
+  }
+}
+
+/// Decodes percent-encoded bytes, invalid sequences are left intact.
+pub(crate) fn percent_decode(value: &str) -> String {
+  let bytes = value.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+  while index < bytes.len() {
+    let hex = bytes
+      .get(index + 1..index + 3)
+      .and_then(|hex| std::str::from_utf8(hex).ok());
+    match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+      Some(byte) if bytes[index] == b'%' => {
+        decoded.push(byte);
+        index += 3;
+      }
+      _ => {
+        decoded.push(bytes[index]);
+        index += 1;
+      }
+    }
+  }
+  String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Converts `HTML` document into data URL string.
+pub fn html_data_url(html: &str) -> String {
+  let mut url = String::from("data:text/html;charset=utf-8,");
+  for byte in html.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => url.push(byte as char),
+      other => url.push_str(&format!("%{:02X}", other)),
+    }
+  }
+  url
+}
+
+/// Converts the value into JavaScript string literal.
+pub fn js_string(value: &str) -> String {
+  let mut literal = String::from("\"");
+  for ch in value.chars() {
+    match ch {
+      '"' => literal.push_str("\\\""),
+      '\\' => literal.push_str("\\\\"),
+      '\n' => literal.push_str("\\n"),
+      '\r' => literal.push_str("\\r"),
+      '\t' => literal.push_str("\\t"),
+      ch if ch.is_control() || ch == '\u{2028}' || ch == '\u{2029}' => {
+        literal.push_str(&format!("\\u{:04x}", ch as u32))
+      }
+      ch => literal.push(ch),
+    }
+  }
+  literal.push('"');
+  literal
+}
+
 /// Replaces the extension to `.pdf`.
 pub fn replace_ext(path: &Path) -> String {
   path.with_extension(PDF_EXTENSION).to_string_lossy().to_string()
@@ -63,10 +231,48 @@ pub fn file_name(path: &Path) -> Result<String> {
   )
 }
 
-/// Returns `true` when specified path has `HTML` file extension.
-pub fn has_html_extension(path: &Path) -> bool {
+/// Compares strings in natural order, numbers inside strings are compared by their values
+/// and letters are compared case-insensitively, so `Chapter2.html` precedes `chapter10.html`.
+pub fn natural_cmp(left: &str, right: &str) -> CmpOrdering {
+  let (mut left_chars, mut right_chars) = (left.chars().peekable(), right.chars().peekable());
+  loop {
+    match (left_chars.peek().copied(), right_chars.peek().copied()) {
+      (None, None) => return left.cmp(right),
+      (None, Some(_)) => return CmpOrdering::Less,
+      (Some(_), None) => return CmpOrdering::Greater,
+      (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+        let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+          let mut digits = String::new();
+          while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            digits.push(digit);
+          }
+          digits.trim_start_matches('0').to_string()
+        };
+        let (l, r) = (number(&mut left_chars), number(&mut right_chars));
+        let ordering = l.len().cmp(&r.len()).then_with(|| l.cmp(&r));
+        if ordering.is_ne() {
+          return ordering;
+        }
+      }
+      (Some(l), Some(r)) => {
+        let ordering = l.to_lowercase().cmp(r.to_lowercase());
+        if ordering.is_ne() {
+          return ordering;
+        }
+        left_chars.next();
+        right_chars.next();
+      }
+    }
+  }
+}
+
+/// Returns `true` when specified path has one of the extensions, compared case-insensitively.
+pub fn has_extension(path: &Path, extensions: &[String]) -> bool {
   if let Some(extension) = path.extension() {
-    extension == HTML_EXTENSION
+    let extension = extension.to_string_lossy();
+    extensions
+      .iter()
+      .any(|expected| expected.trim_start_matches('.').eq_ignore_ascii_case(&extension))
   } else {
     false
   }
@@ -83,3 +289,104 @@ pub fn init_logger(opt_log_level: Option<String>) {
   }
   env_logger::init();
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn base_url_is_inserted_after_head() {
+    let html = insert_base_url(b"<html><HEAD lang=\"en\"><title>T</title>", "file:///a/b&c/");
+    assert_eq!(
+      html,
+      b"<html><HEAD lang=\"en\"><base href=\"file:///a/b&amp;c/\"><title>T</title>"
+    );
+  }
+
+  #[test]
+  fn base_url_is_inserted_at_start_without_head() {
+    let html = insert_base_url(b"<header>X</header>", "http://localhost/");
+    assert_eq!(html, b"<base href=\"http://localhost/\"><header>X</header>");
+  }
+
+  #[test]
+  fn base_url_is_inserted_into_non_utf8_document() {
+    let html = insert_base_url(b"<head>\xe9t\xe9", "http://localhost/");
+    assert_eq!(html, b"<head><base href=\"http://localhost/\">\xe9t\xe9");
+  }
+
+  #[test]
+  fn file_name_is_derived_from_last_path_segment() {
+    assert_eq!(url_file_name("http://localhost:3000/report?id=1"), "report.pdf");
+    assert_eq!(url_file_name("https://example.com/docs/guide.html#intro"), "guide.pdf");
+    assert_eq!(url_file_name("https://example.com/docs/guide/"), "guide.pdf");
+    assert_eq!(url_file_name("file:///home/user/archive.tar.gz"), "archive.tar.pdf");
+    assert_eq!(url_file_name("https://example.com/.profile"), ".profile.pdf");
+  }
+
+  #[test]
+  fn file_name_is_percent_decoded_and_sanitized() {
+    assert_eq!(
+      url_file_name("https://example.com/annual%20report.html"),
+      "annual report.pdf"
+    );
+    assert_eq!(url_file_name("https://example.com/a%2Fb%3Fc.html"), "a_b_c.pdf");
+    assert_eq!(url_file_name("https://example.com/100%25"), "100%.pdf");
+    assert_eq!(url_file_name("https://example.com/bad%zz"), "bad%zz.pdf");
+  }
+
+  #[test]
+  fn file_name_falls_back_to_host_name() {
+    assert_eq!(url_file_name("https://example.com"), "example.com.pdf");
+    assert_eq!(url_file_name("http://localhost:8080/?page=2"), "localhost.pdf");
+  }
+
+  #[test]
+  fn file_name_falls_back_to_document() {
+    assert_eq!(url_file_name("data:text/html,<h1>Hello</h1>"), "document.pdf");
+    assert_eq!(url_file_name("DATA:text/html;base64,PGgxPg=="), "document.pdf");
+    assert_eq!(url_file_name("file:///"), "document.pdf");
+    assert_eq!(url_file_name("https://example.com/%2E%2E"), "document.pdf");
+    assert_eq!(url_file_name("no-scheme"), "document.pdf");
+  }
+
+  #[test]
+  fn path_without_file_name_is_a_read_error() {
+    assert_eq!(file_name(Path::new("reports/summary.html")).unwrap(), "summary.pdf");
+    assert_eq!(file_name(Path::new("/")).unwrap_err().exit_code(), 30);
+  }
+
+  #[test]
+  fn numbers_are_ordered_by_value() {
+    let mut names = vec![
+      "chapter10.html",
+      "Chapter2.html",
+      "chapter1.html",
+      "chapter02b.html",
+      "appendix.html",
+    ];
+    names.sort_by(|left, right| natural_cmp(left, right));
+    assert_eq!(
+      names,
+      [
+        "appendix.html",
+        "chapter1.html",
+        "Chapter2.html",
+        "chapter02b.html",
+        "chapter10.html"
+      ]
+    );
+  }
+
+  #[test]
+  fn equal_names_are_ordered_consistently() {
+    assert_eq!(natural_cmp("a01", "a1"), "a01".cmp("a1"));
+    assert_eq!(natural_cmp("A", "a"), "A".cmp("a"));
+    assert_eq!(natural_cmp("a", "a"), CmpOrdering::Equal);
+    assert_eq!(natural_cmp("a", "a1"), CmpOrdering::Less);
+    assert_eq!(
+      natural_cmp("99999999999999999999999", "100000000000000000000000"),
+      CmpOrdering::Less
+    );
+  }
+}
@@ -0,0 +1,296 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2023 senees
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ *
+ * Apache license, Version 2.0
+ *
+ * Copyright (c) 2023 senees
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Waiting for the page to be ready for printing
+//!
+//! Pages rendering charts or fetching data with JavaScript are often not complete
+//! when navigation finishes. Wait conditions delay printing until the page is ready,
+//! each condition is checked with its own timeout.
+
+use crate::errors::{err_chrome_launch, err_wait, err_wait_timeout, Result};
+use crate::utils::js_string;
+use headless_chrome::protocol::cdp::types::Event;
+use headless_chrome::protocol::cdp::Network;
+use headless_chrome::Tab;
+use serde_json::json;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default timeout of a single wait condition.
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval between subsequent checks of a wait condition.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Name of the property of `window` keeping the state of the evaluated wait expression.
+const EXPRESSION_STATE: &str = "__htopWaitForExpression";
+
+/// Condition the page must satisfy before printing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaitCondition {
+  /// An element matching the CSS selector is present in the document.
+  Selector(String),
+  /// JavaScript expression evaluates to `true`, returned promises are awaited until the wait times out.
+  Expression(String),
+  /// All fonts used in the document are loaded (`document.fonts.ready`).
+  Fonts,
+  /// No network requests were in flight for the specified time.
+  NetworkIdle(Duration),
+  /// Fixed delay.
+  Delay(Duration),
+}
+
+impl fmt::Display for WaitCondition {
+  /// Implements [Display](fmt::Display) trait for [WaitCondition].
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Selector(selector) => write!(f, "selector '{}'", selector),
+      Self::Expression(expression) => write!(f, "expression '{}'", expression),
+      Self::Fonts => write!(f, "document fonts"),
+      Self::NetworkIdle(idle) => write!(f, "network idle for {}ms", idle.as_millis()),
+      Self::Delay(delay) => write!(f, "delay of {}ms", delay.as_millis()),
+    }
+  }
+}
+
+/// Wait condition with its own timeout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wait {
+  /// Condition to be satisfied.
+  pub condition: WaitCondition,
+  /// Maximal time of waiting for the condition, not applicable to delays.
+  pub timeout: Duration,
+}
+
+impl Wait {
+  /// Creates a new wait for specified condition with specified timeout.
+  pub fn new(condition: WaitCondition, timeout: Duration) -> Self {
+    Self { condition, timeout }
+  }
+}
+
+/// State of network requests observed in a browser tab.
+struct NetworkState {
+  /// Identifiers of requests in flight.
+  in_flight: HashSet<String>,
+  /// Time of the last started or finished request.
+  last_activity: Instant,
+}
+
+/// Monitor of network requests in flight in a browser tab.
+pub struct NetworkMonitor {
+  /// Network state shared with the event listener registered in the tab.
+  state: Arc<Mutex<NetworkState>>,
+}
+
+impl NetworkMonitor {
+  /// Enables network events in the tab and starts monitoring requests.
+  pub fn attach(tab: &Tab) -> Result<Self> {
+    let state = Arc::new(Mutex::new(NetworkState {
+      in_flight: HashSet::new(),
+      last_activity: Instant::now(),
+    }));
+    // built from JSON, so that optional parameters added in newer protocol versions are left out
+    let enable: Network::Enable = serde_json::from_value(json!({})).map_err(err_chrome_launch)?;
+    tab.call_method(enable).map_err(err_chrome_launch)?;
+    let listener_state = Arc::clone(&state);
+    tab
+      .add_event_listener(Arc::new(move |event: &Event| {
+        let mut state = listener_state.lock().unwrap();
+        match event {
+          Event::NetworkRequestWillBeSent(event) => {
+            state.in_flight.insert(event.params.request_id.clone());
+          }
+          Event::NetworkLoadingFinished(event) => {
+            state.in_flight.remove(&event.params.request_id);
+          }
+          Event::NetworkLoadingFailed(event) => {
+            state.in_flight.remove(&event.params.request_id);
+          }
+          _ => return,
+        }
+        state.last_activity = Instant::now();
+      }))
+      .map_err(err_chrome_launch)?;
+    Ok(Self { state })
+  }
+
+  /// Forgets requests of previously loaded pages, called before navigating to the next page.
+  pub fn reset(&self) {
+    let mut state = self.state.lock().unwrap();
+    state.in_flight.clear();
+    state.last_activity = Instant::now();
+  }
+
+  /// Returns the time elapsed since the last network activity, `None` when requests are in flight.
+  fn idle_time(&self) -> Option<Duration> {
+    let state = self.state.lock().unwrap();
+    state.in_flight.is_empty().then(|| state.last_activity.elapsed())
+  }
+}
+
+/// Waits until the page loaded in the tab satisfies the condition.
+///
+/// Waiting for network idle requires the [NetworkMonitor] attached to the tab before navigation.
+pub fn wait_for(tab: &Tab, wait: &Wait, network: Option<&NetworkMonitor>) -> Result<()> {
+  let condition = &wait.condition;
+  match condition {
+    WaitCondition::Selector(selector) => poll(wait, || {
+      evaluate_bool(
+        tab,
+        condition,
+        &format!("document.querySelector({}) !== null", js_string(selector)),
+        false,
+      )
+    }),
+    // returned promises are not awaited by the browser, their outcome is polled instead,
+    // so promises that never settle time out like any other expression
+    WaitCondition::Expression(expression) => {
+      // forget the evaluation left by the previous wait that timed out
+      evaluate_bool(tab, condition, &format!("delete window.{EXPRESSION_STATE}"), false)?;
+      poll(wait, || {
+        evaluate_bool(tab, condition, &expression_script(expression), false)
+      })
+    }
+    WaitCondition::Fonts => {
+      let script = format!(
+        "Promise.race([document.fonts.ready.then(() => true), new Promise(resolve => setTimeout(() => resolve(false), {}))])",
+        wait.timeout.as_millis()
+      );
+      if evaluate_bool(tab, condition, &script, true)? {
+        Ok(())
+      } else {
+        Err(err_wait_timeout(&condition.to_string(), wait.timeout))
+      }
+    }
+    WaitCondition::NetworkIdle(idle) => {
+      let Some(network) = network else {
+        return Err(err_wait(
+          &condition.to_string(),
+          "network monitor is not attached to the tab",
+        ));
+      };
+      poll(wait, || {
+        Ok(network.idle_time().is_some_and(|idle_time| idle_time >= *idle))
+      })
+    }
+    WaitCondition::Delay(delay) => {
+      thread::sleep(*delay);
+      Ok(())
+    }
+  }
+}
+
+/// Repeatedly checks the condition until it is satisfied or the timeout elapses.
+fn poll(wait: &Wait, mut check: impl FnMut() -> Result<bool>) -> Result<()> {
+  let started = Instant::now();
+  loop {
+    if check()? {
+      return Ok(());
+    }
+    if started.elapsed() >= wait.timeout {
+      return Err(err_wait_timeout(&wait.condition.to_string(), wait.timeout));
+    }
+    thread::sleep(POLL_INTERVAL);
+  }
+}
+
+/// Returns the script checking the outcome of the expression evaluated in the previous check.
+///
+/// The first check starts the evaluation and keeps its state in the page, next checks return
+/// `true` when the expression (or the promise it returned) resolved to a truthy value.
+/// A falsy or rejected outcome is forgotten, so the expression is evaluated again.
+fn expression_script(expression: &str) -> String {
+  format!(
+    r#"(() => {{
+  const state = window.{EXPRESSION_STATE};
+  if (state === undefined) {{
+    const started = window.{EXPRESSION_STATE} = {{ done: false, value: false }};
+    Promise.resolve()
+      .then(() => ({expression}))
+      .then(value => {{ started.value = Boolean(value); }}, () => {{}})
+      .finally(() => {{ started.done = true; }});
+    return false;
+  }}
+  if (!state.done) {{
+    return false;
+  }}
+  delete window.{EXPRESSION_STATE};
+  return state.value;
+}})()"#
+  )
+}
+
+/// Evaluates the script in the tab and returns `true` when the result is boolean `true`,
+/// the promise returned by the script is awaited when `await_promise` flag is set.
+///
+/// Scripts throwing exceptions are evaluated as `false`.
+fn evaluate_bool(tab: &Tab, condition: &WaitCondition, script: &str, await_promise: bool) -> Result<bool> {
+  let result = tab
+    .evaluate(script, await_promise)
+    .map_err(|e| err_wait(&condition.to_string(), e))?;
+  Ok(result.value.and_then(|value| value.as_bool()).unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::converter::open_browser;
+  use crate::options::PdfPrintingOptions;
+
+  #[test]
+  #[ignore = "requires headless chrome"]
+  fn unsettled_promise_times_out() {
+    let browser = open_browser(&PdfPrintingOptions::default(), Duration::from_secs(30)).unwrap();
+    let tab = browser.new_tab().unwrap();
+    tab.navigate_to("data:text/html,<p>waiting</p>").unwrap();
+    tab.wait_until_navigated().unwrap();
+    let wait = Wait::new(
+      WaitCondition::Expression("new Promise(() => {})".to_string()),
+      Duration::from_millis(500),
+    );
+    let started = Instant::now();
+    let reason = wait_for(&tab, &wait, None).unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(reason.exit_code(), 24);
+    assert_eq!(
+      reason.to_string(),
+      err_wait_timeout(&wait.condition.to_string(), wait.timeout).to_string()
+    );
+    let wait = Wait::new(
+      WaitCondition::Expression("new Promise(resolve => setTimeout(() => resolve(true), 200))".to_string()),
+      Duration::from_secs(5),
+    );
+    wait_for(&tab, &wait, None).unwrap();
+  }
+}